@@ -0,0 +1,44 @@
+use bd::map::MapDescription;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{fs, path::PathBuf};
+
+// map text with steel around and random tiles inside
+fn generate_map_text(width: usize, height: usize) -> String {
+    let mut rng = StdRng::seed_from_u64(0);
+    let inside = ['.', '.', '.', ' ', '%', 'o', '*', 'x'];
+    let mut text = String::with_capacity((width + 1) * height);
+    for y in 0..height {
+        for x in 0..width {
+            text.push(if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+                '#'
+            } else if x == 1 && y == 1 {
+                's'
+            } else {
+                inside[rng.gen_range(0, inside.len())]
+            });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+fn load(c: &mut Criterion) {
+    let mut group = c.benchmark_group("map load");
+    group.sample_size(10);
+    for size in &[256, 1024, 2048] {
+        let text = generate_map_text(*size, *size);
+        let path: PathBuf = std::env::temp_dir().join(format!("bd-bench-map-{}.txt", size));
+        fs::write(&path, &text).expect("temp file writable");
+        group.throughput(Throughput::Bytes(text.len() as u64));
+
+        group.bench_with_input(BenchmarkId::from_parameter(size), &path, |b, path| {
+            b.iter(|| MapDescription::load(path.clone()).expect("map should load"))
+        });
+        let _ = fs::remove_file(&path);
+    }
+    group.finish();
+}
+
+criterion_group!(benches, load);
+criterion_main!(benches);
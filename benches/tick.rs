@@ -0,0 +1,151 @@
+use bd::{
+    grid::{CreatureKind, GridPos, GridState, TileType},
+    input::Direction,
+    map::MapDescription,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::path::PathBuf;
+
+// percentages of the inside of a generated cave
+struct Mix {
+    dirt: u32,
+    wall: u32,
+    empty: u32,
+    rock: u32,
+    diamond: u32,
+    // creatures take whatever is left
+}
+
+const MOSTLY_DIRT: Mix = Mix {
+    dirt: 60,
+    wall: 20,
+    empty: 10,
+    rock: 6,
+    diamond: 3,
+};
+
+const DENSE_ROCKS: Mix = Mix {
+    dirt: 10,
+    wall: 5,
+    empty: 35,
+    rock: 40,
+    diamond: 10,
+};
+
+const MANY_CREATURES: Mix = Mix {
+    dirt: 10,
+    wall: 10,
+    empty: 40,
+    rock: 5,
+    diamond: 5,
+};
+
+fn generate_cave(size: usize, mix: &Mix) -> MapDescription {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut tiles = Vec::with_capacity(size * size);
+    for i in 0..size * size {
+        let (x, y) = GridPos::new(i).to_xy(size);
+        tiles.push(if x == 0 || y == 0 || x == size - 1 || y == size - 1 {
+            TileType::Steel
+        } else {
+            let mut roll = rng.gen_range(0, 100);
+            let mut pick = |chance: u32| {
+                let hit = roll < chance;
+                roll = roll.saturating_sub(chance);
+                hit
+            };
+            if pick(mix.dirt) {
+                TileType::Dirt
+            } else if pick(mix.wall) {
+                TileType::Wall
+            } else if pick(mix.empty) {
+                TileType::Empty
+            } else if pick(mix.rock) {
+                TileType::Rock
+            } else if pick(mix.diamond) {
+                TileType::Diamond
+            } else {
+                TileType::Creature {
+                    kind: CreatureKind::Firefly,
+                    counter: 0,
+                    direction: Direction::Left,
+                }
+            }
+        });
+    }
+    let start = GridPos::from_xy(1, 1, size);
+    tiles[start.index()] = TileType::Player;
+
+    MapDescription {
+        tiles,
+        width: size,
+        height: size,
+        start,
+        params: Default::default(),
+    }
+}
+
+// ticks it takes most of the loose rocks to come down; in the big caves
+// some of them keep falling for much longer
+const SETTLE_TICKS: usize = 100;
+
+// let the loose rocks fall down first, to measure the steady state
+fn settled(map: MapDescription) -> GridState {
+    let mut grid = GridState::from_map(map, 0);
+    for _ in 0..SETTLE_TICKS {
+        grid.run_tick(vec![]);
+    }
+    grid
+}
+
+fn bundled_maps(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick bundled");
+    for name in &["01", "02"] {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("resources/map")
+            .join(format!("{}.txt", name));
+        let map = MapDescription::load(path).expect("map should load");
+        group.throughput(Throughput::Elements(map.tiles.len() as u64));
+
+        let mut grid = GridState::from_map(map, 0);
+        group.bench_function(*name, |b| b.iter(|| grid.run_tick(vec![])));
+    }
+    group.finish();
+}
+
+fn generated_caves(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick generated");
+    group.sample_size(20);
+    for (name, mix) in &[
+        ("mostly dirt", MOSTLY_DIRT),
+        ("dense rocks", DENSE_ROCKS),
+        ("many creatures", MANY_CREATURES),
+    ] {
+        for size in &[256, 4096] {
+            group.throughput(Throughput::Elements((size * size) as u64));
+            let grid = settled(generate_cave(*size, mix));
+
+            group.bench_with_input(BenchmarkId::new(*name, size), &grid, |b, grid| {
+                let mut grid = grid.clone();
+                b.iter(|| grid.run_tick(vec![]))
+            });
+            // what it would cost without skipping the sleeping cells
+            group.bench_with_input(
+                BenchmarkId::new(format!("{} full scan", name), size),
+                &grid,
+                |b, grid| {
+                    let mut grid = grid.clone();
+                    b.iter(|| {
+                        grid.wake_all();
+                        grid.run_tick(vec![])
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bundled_maps, generated_caves);
+criterion_main!(benches);
@@ -0,0 +1,157 @@
+use amethyst::{
+    config::Config,
+    input::{Bindings, Button, StringBindings, VirtualKeyCode},
+};
+use anyhow::{bail, Result};
+use std::path::{Path, PathBuf};
+
+/// Actions the player can rebind, with their names as shown in the settings
+pub const ACTIONS: [(&str, &str); 8] = [
+    ("up", "Up"),
+    ("down", "Down"),
+    ("left", "Left"),
+    ("right", "Right"),
+    ("fire", "Fire"),
+    ("restart", "Restart"),
+    ("zoom_in", "Zoom in"),
+    ("zoom_out", "Zoom out"),
+];
+
+/// Keys the game uses for itself, which can't be bound to actions: pausing,
+/// switching themes and opening the settings
+pub const RESERVED_KEYS: [VirtualKeyCode; 3] = [
+    VirtualKeyCode::Escape,
+    VirtualKeyCode::T,
+    VirtualKeyCode::F1,
+];
+
+/// The bindings that come with the game
+pub const DEFAULT_BINDINGS: &str = "./config/bindings.ron";
+
+/// Where the bindings the player changed are kept
+pub fn user_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("bd").join("bindings.ron"))
+}
+
+/// Bindings of `default_path`, with every action the player rebound replaced
+/// by their own
+pub fn load(default_path: &Path) -> Result<Bindings<StringBindings>> {
+    let mut bindings = Bindings::<StringBindings>::load_no_fallback(default_path)?;
+
+    if let Some(path) = user_path().filter(|path| path.is_file()) {
+        let user = match Bindings::<StringBindings>::load_no_fallback(&path) {
+            Ok(user) => user,
+            Err(e) => {
+                log::warn!("Ignoring bindings in {:?}: {}", path, e);
+                Bindings::default()
+            }
+        };
+        for action in user.actions() {
+            clear_action(&mut bindings, action, |_| true);
+        }
+        for action in user.actions() {
+            for combination in user.action_bindings(action) {
+                // a key the player took for another action isn't left on
+                // its default one
+                let actions: Vec<String> = bindings.actions().cloned().collect();
+                for other in &actions {
+                    clear_action(&mut bindings, other, |other| {
+                        same_buttons(other, combination)
+                    });
+                }
+                bindings.insert_action_binding(action.clone(), combination.iter().copied())?;
+            }
+        }
+    }
+
+    bindings.check_invariants()?;
+    Ok(bindings)
+}
+
+/// Save the actions of `bindings` that aren't bound like in `default_path`
+/// as the player's own, so the rest keeps following the defaults
+pub fn save_user(bindings: &Bindings<StringBindings>, default_path: &Path) -> Result<()> {
+    let defaults = Bindings::<StringBindings>::load_no_fallback(default_path)?;
+    let mut user = Bindings::<StringBindings>::default();
+    for action in bindings.actions() {
+        let same = |a: &Bindings<StringBindings>, b: &Bindings<StringBindings>| {
+            a.action_bindings(action).all(|combination| {
+                b.action_bindings(action)
+                    .any(|other| same_buttons(combination, other))
+            })
+        };
+        if same(bindings, &defaults) && same(&defaults, bindings) {
+            continue;
+        }
+        for combination in bindings.action_bindings(action) {
+            user.insert_action_binding(action.clone(), combination.iter().copied())?;
+        }
+    }
+
+    let path = user_path().ok_or_else(|| anyhow::format_err!("No config directory"))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    user.write(path)?;
+    Ok(())
+}
+
+/// Make `key` the only key of `action`, taking it away from any other action
+/// it was bound to; controller bindings stay as they were
+pub fn rebind(bindings: &mut Bindings<StringBindings>, action: &str, key: Button) -> Result<()> {
+    if let Button::Key(key) = key {
+        if RESERVED_KEYS.contains(&key) {
+            bail!("{:?} is taken by the game", key);
+        }
+    }
+
+    let actions: Vec<String> = bindings.actions().cloned().collect();
+    for other in &actions {
+        clear_action(bindings, other, |combination| combination.contains(&key));
+    }
+    clear_action(bindings, action, |combination| {
+        combination.iter().any(|button| match button {
+            Button::Key(_) | Button::ScanCode(_) => true,
+            _ => false,
+        })
+    });
+
+    bindings.insert_action_binding(action.to_string(), Some(key))?;
+    Ok(())
+}
+
+// remove the combinations of `action` matching `filter`
+fn clear_action(
+    bindings: &mut Bindings<StringBindings>,
+    action: &str,
+    filter: impl Fn(&[Button]) -> bool,
+) {
+    let combinations: Vec<Vec<Button>> = bindings
+        .action_bindings(action)
+        .filter(|combination| filter(combination))
+        .map(|combination| combination.to_vec())
+        .collect();
+    for combination in combinations {
+        bindings
+            .remove_action_binding(action, &combination)
+            .expect("binding was just there");
+    }
+}
+
+// whether two combinations press the same buttons, in any order
+fn same_buttons(a: &[Button], b: &[Button]) -> bool {
+    a.len() == b.len() && a.iter().all(|button| b.contains(button))
+}
+
+/// How a binding is shown to the player
+pub fn describe(combination: &[Button]) -> String {
+    let names: Vec<String> = combination
+        .iter()
+        .map(|button| match button {
+            Button::Key(key) => format!("{:?}", key),
+            Button::Controller(_, button) => format!("Pad {:?}", button),
+            button => format!("{:?}", button),
+        })
+        .collect();
+    names.join("+")
+}
@@ -0,0 +1,80 @@
+use anyhow::Result;
+use image::RgbaImage;
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::PathBuf,
+};
+
+use crate::{
+    game,
+    grid::GridState,
+    map::MapDescription,
+    render::{GifWriter, Renderer},
+    replay::Replay,
+    theme::DEFAULT_THEME,
+};
+
+/// Render a map to a PNG image, or with a replay, every tick of it to
+/// numbered PNG images in the `output` directory, up to the end of the cave
+pub fn png(map_path: PathBuf, output: PathBuf, replay_path: Option<PathBuf>) -> Result<()> {
+    let map = MapDescription::load(map_path)?;
+    let replay = match replay_path {
+        Some(path) => Replay::load(path)?,
+        None => {
+            let grid = GridState::from_map(map, 0);
+            let renderer = Renderer::load(DEFAULT_THEME, grid.params().palette)?;
+            renderer.render(&grid, 0.).save(output)?;
+            return Ok(());
+        }
+    };
+
+    fs::create_dir_all(&output)?;
+    let grid = GridState::from_map(map, replay.seed);
+    let renderer = Renderer::load(DEFAULT_THEME, grid.params().palette)?;
+    play(grid, &renderer, replay, |grid, image| {
+        image.save(output.join(format!("{:05}.png", grid.tick_count)))?;
+        Ok(())
+    })
+}
+
+/// Simulate a replay and save it as an animated GIF running at the speed
+/// of the game
+pub fn gif(map_path: PathBuf, replay_path: PathBuf, output: PathBuf) -> Result<()> {
+    let map = MapDescription::load(map_path)?;
+    let replay = Replay::load(replay_path)?;
+
+    let grid = GridState::from_map(map, replay.seed);
+    let renderer = Renderer::load(DEFAULT_THEME, grid.params().palette)?;
+    let mut writer = GifWriter::new(
+        BufWriter::new(File::create(output)?),
+        grid.width() as u32 * renderer.tile_size(),
+        grid.height() as u32 * renderer.tile_size(),
+    )?;
+    play(grid, &renderer, replay, |_, image| {
+        writer.add_frame(image, game::tick_duration())
+    })
+}
+
+// render the grid before the replay, and after every tick of it until
+// the cave is over; the grid stands still after that
+fn play(
+    mut grid: GridState,
+    renderer: &Renderer,
+    replay: Replay,
+    mut frame: impl FnMut(&GridState, &RgbaImage) -> Result<()>,
+) -> Result<()> {
+    let tick_duration = game::tick_duration().as_secs_f64();
+
+    frame(&grid, &renderer.render(&grid, 0.))?;
+    for actions in replay.ticks {
+        if game::cave_end(&grid).is_some() {
+            break;
+        }
+        game::replay_tick(&mut grid, actions);
+        let time = grid.tick_count as f64 * tick_duration;
+        frame(&grid, &renderer.render(&grid, time))?;
+    }
+
+    Ok(())
+}
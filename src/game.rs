@@ -0,0 +1,115 @@
+//! The part of playing a cave every frontend shares
+use anyhow::Result;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    grid::{GridState, Outcome},
+    input::{Action, InputTracker},
+    replay::Replay,
+    session::CaveEnd,
+};
+
+/// Rate of the fixed updates of the game loop, in Hz
+pub const FIXED_UPDATES_PER_SECOND: u64 = 60;
+/// The grid advances once per this many fixed updates
+pub const FIXED_UPDATES_PER_TICK: u64 = 8;
+
+/// Wall clock time between two grid ticks
+pub fn tick_duration() -> Duration {
+    cave_time(1)
+}
+
+/// How long a cave has been going on after `ticks` ticks
+pub fn cave_time(ticks: u64) -> Duration {
+    Duration::from_nanos(1_000_000_000 * FIXED_UPDATES_PER_TICK * ticks / FIXED_UPDATES_PER_SECOND)
+}
+
+/// Time left to complete the cave in, if it has a limit
+pub fn time_left(grid: &GridState) -> Option<Duration> {
+    grid.params().time.map(|seconds| {
+        Duration::from_secs(seconds)
+            .checked_sub(cave_time(grid.tick_count))
+            .unwrap_or_default()
+    })
+}
+
+/// Whether the time ran out before the cave was over
+pub fn out_of_time(grid: &GridState) -> bool {
+    grid.outcome() == Outcome::Playing && time_left(grid) == Some(Duration::default())
+}
+
+/// How the cave ended, if it did
+pub fn cave_end(grid: &GridState) -> Option<CaveEnd> {
+    match grid.outcome() {
+        Outcome::Completed => Some(CaveEnd::Completed),
+        Outcome::Died => Some(CaveEnd::Died),
+        Outcome::Playing if out_of_time(grid) => Some(CaveEnd::OutOfTime),
+        Outcome::Playing => None,
+    }
+}
+
+/// How the cave is going, as shown to the player
+pub fn describe_end(end: Option<CaveEnd>) -> &'static str {
+    match end {
+        None => "Playing",
+        Some(CaveEnd::Completed) => "Completed",
+        Some(CaveEnd::Died) => "Died",
+        Some(CaveEnd::OutOfTime) => "Out of time",
+        Some(CaveEnd::Quit) => "Quit",
+    }
+}
+
+/// Points scored in the cave so far: the diamonds, with the ones past the
+/// ones needed worth more, the creatures killed, and once the cave is
+/// completed, the seconds left
+pub fn score(grid: &GridState) -> usize {
+    let params = grid.params();
+    let needed = grid.diamond_count.min(params.diamonds_needed);
+    let extra = grid.diamond_count - needed;
+
+    let mut score = needed * params.diamond_value
+        + extra * params.extra_diamond_value
+        + grid.creatures_killed * params.creature_value;
+    if grid.outcome() == Outcome::Completed {
+        let seconds_left = time_left(grid).map_or(0, |left| left.as_secs() as usize);
+        score += seconds_left * params.time_bonus;
+    }
+    score
+}
+
+/// Advance the grid one tick with whatever input is buffered, recording it;
+/// once the time is out the cave stands still
+pub fn run_tick(grid: &mut GridState, input: &mut InputTracker, replay: &mut Replay) {
+    if out_of_time(grid) {
+        return;
+    }
+    let actions = input.pop_action();
+    replay.record(&actions);
+    grid.run_tick(actions);
+}
+
+/// Advance the grid one tick with the recorded `actions` of a replay; like
+/// in the game, once the time is out the cave stands still
+pub fn replay_tick(grid: &mut GridState, actions: Vec<Action>) {
+    if out_of_time(grid) {
+        return;
+    }
+    grid.run_tick(actions);
+}
+
+/// Where the replay of the last game is kept
+pub fn last_replay_path() -> PathBuf {
+    Path::new("./replays").join("last.txt")
+}
+
+/// Keep the replay of the game that just ended in `replays/last.txt`
+pub fn save_last_replay(replay: &Replay) -> Result<()> {
+    let path = last_replay_path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    replay.save(path)
+}
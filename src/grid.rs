@@ -1,8 +1,8 @@
-use rand::{thread_rng, Rng};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
 
 use crate::{
     input::{self, Direction},
-    map::MapDescription,
+    map::{CaveParams, MapDescription},
 };
 
 #[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
@@ -35,6 +35,10 @@ impl GridPos {
         }
     }
 
+    pub fn index(self) -> usize {
+        self.0
+    }
+
     pub fn to_xy(self, width: usize) -> (usize, usize) {
         let x = self.0 % width;
         let y = self.0 / width;
@@ -73,12 +77,21 @@ pub enum TileType {
     Wall,
     Diamond,
     Steel,
+    Exit,
     Creature {
+        kind: CreatureKind,
         counter: usize,
         direction: Direction,
     },
 }
 
+/// The creatures of the original; here they only differ in looks
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CreatureKind {
+    Firefly,
+    Butterfly,
+}
+
 impl Default for TileType {
     fn default() -> Self {
         TileType::Empty
@@ -86,21 +99,27 @@ impl Default for TileType {
 }
 
 impl TileType {
-    pub fn to_sprite_number(self) -> Option<usize> {
+    /// Name of the tile kind, as used in the resource files
+    pub fn kind_name(self) -> &'static str {
         use TileType::*;
-        Some(match self {
-            Empty => return None,
-            Player => 0,
-            Dirt => 2,
-            Rock => 3,
-            Steel => 4,
-            Wall => 5,
-            Diamond => 6,
+        match self {
+            Empty => "empty",
+            Player => "player",
+            Dirt => "dirt",
+            Rock => "rock",
+            Wall => "wall",
+            Diamond => "diamond",
+            Steel => "steel",
+            Exit => "exit",
             Creature {
-                counter: _,
-                direction: _,
-            } => 7,
-        })
+                kind: CreatureKind::Firefly,
+                ..
+            } => "firefly",
+            Creature {
+                kind: CreatureKind::Butterfly,
+                ..
+            } => "butterfly",
+        }
     }
 
     fn can_fall(self) -> bool {
@@ -136,6 +155,7 @@ impl TileType {
             Empty => true,
             Dirt => true,
             Diamond => true,
+            Exit => true,
             _ => false,
         }
     }
@@ -156,6 +176,22 @@ impl TileType {
         }
     }
 
+    fn is_creature(self) -> bool {
+        use TileType::*;
+        match self {
+            Creature { .. } => true,
+            _ => false,
+        }
+    }
+
+    fn is_exit(self) -> bool {
+        use TileType::*;
+        match self {
+            Exit => true,
+            _ => false,
+        }
+    }
+
     fn is_diamond(self) -> bool {
         use TileType::*;
         match self {
@@ -173,37 +209,268 @@ impl TileType {
     }
 }
 
-#[derive(Default, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Playing,
+    Completed,
+    Died,
+}
+
+/// Something that happened on the grid during a tick
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GridEvent {
+    Moved {
+        from: GridPos,
+        to: GridPos,
+        kind: TileType,
+    },
+    // the tile stayed in place, but its kind changed
+    Changed {
+        pos: GridPos,
+        from: TileType,
+        to: TileType,
+    },
+    DiamondCollected {
+        pos: GridPos,
+    },
+    ExitEntered {
+        pos: GridPos,
+    },
+}
+
+impl GridEvent {
+    /// Positions of all the tiles affected by this event
+    pub fn positions(self) -> Vec<GridPos> {
+        use GridEvent::*;
+        match self {
+            Moved { from, to, .. } => vec![from, to],
+            Changed { pos, .. } => vec![pos],
+            DiamondCollected { pos } => vec![pos],
+            ExitEntered { pos } => vec![pos],
+        }
+    }
+}
+
+/// Set of cell indices, one bit per cell
+///
+/// Big areas without any cells in the set are skipped 64 cells at a time.
+#[derive(Clone, Default)]
+struct CellSet {
+    words: Vec<u64>,
+}
+
+impl CellSet {
+    fn empty(len: usize) -> Self {
+        CellSet {
+            words: vec![0; (len + 63) / 64],
+        }
+    }
+
+    fn full(len: usize) -> Self {
+        let mut words = vec![!0u64; (len + 63) / 64];
+        if len % 64 != 0 {
+            if let Some(last) = words.last_mut() {
+                *last = (1 << (len % 64)) - 1;
+            }
+        }
+        CellSet { words }
+    }
+
+    fn insert(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    fn remove(&mut self, i: usize) {
+        self.words[i / 64] &= !(1 << (i % 64));
+    }
+
+    fn contains(&self, i: usize) -> bool {
+        self.words
+            .get(i / 64)
+            .map_or(false, |word| word & (1 << (i % 64)) != 0)
+    }
+
+    // the lowest index in the set that is not lower than `i`
+    fn next_from(&self, i: usize) -> Option<usize> {
+        let mut word_i = i / 64;
+        let mut word = *self.words.get(word_i)? & (!0u64 << (i % 64));
+        loop {
+            if word != 0 {
+                return Some(word_i * 64 + word.trailing_zeros() as usize);
+            }
+            word_i += 1;
+            word = *self.words.get(word_i)?;
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct GridState {
     height: usize,
     width: usize,
-    pub tiles: Vec<TileType>,
+    tiles: Vec<TileType>,
 
     pub player_pos: GridPos,
 
     // TODO: move?
     pub diamond_count: usize,
+    pub creatures_killed: usize,
+
+    // number of ticks simulated so far
+    pub tick_count: u64,
+    // everything that happened during the last tick
+    pub events: Vec<GridEvent>,
+    completed: bool,
+
+    // all the randomness must come from here, so replays are deterministic
+    seed: u64,
+    rng: StdRng,
+
+    // cells that might do something during the next tick; anything else
+    // is known to stay put until one of its neighbours changes
+    active: CellSet,
+    // cells that something fell into during the last tick, and during the
+    // one being run
+    fell: CellSet,
+    falling: CellSet,
+
+    params: CaveParams,
+}
+
+impl Default for GridState {
+    fn default() -> Self {
+        GridState {
+            height: 0,
+            width: 0,
+            tiles: vec![],
+            player_pos: GridPos::default(),
+            diamond_count: 0,
+            creatures_killed: 0,
+            tick_count: 0,
+            events: vec![],
+            completed: false,
+            seed: 0,
+            rng: StdRng::seed_from_u64(0),
+            active: CellSet::default(),
+            fell: CellSet::default(),
+            falling: CellSet::default(),
+            params: CaveParams::default(),
+        }
+    }
 }
 
 impl GridState {
     pub fn new() -> Self {
+        let map = crate::map::MapDescription::load("./resources/map/01.txt".into())
+            .expect("map should load");
+
+        Self::from_map(map, thread_rng().gen())
+    }
+
+    pub fn from_map(map: MapDescription, seed: u64) -> Self {
         let MapDescription {
             height,
             width,
             tiles,
             start,
-        } = crate::map::MapDescription::load("./resources/map/01.txt".into())
-            .expect("map should load");
+            params,
+        } = map;
 
-        GridState {
+        let mut grid = GridState {
             height,
             width,
             tiles,
             player_pos: start,
+            params,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
             ..GridState::default()
+        };
+        grid.wake_all();
+        grid
+    }
+
+    /// Make every cell be checked during the next tick, like a scan of the
+    /// whole grid would
+    pub fn wake_all(&mut self) {
+        self.active = CellSet::full(self.tiles.len());
+    }
+
+    // a change at `pos` can make the tile itself, or the ones that
+    // could fall or roll into it, move
+    fn wake_around(&mut self, pos: GridPos) {
+        let i = pos.0;
+        let candidates = [
+            Some(i),
+            i.checked_sub(1),
+            Some(i + 1),
+            Some(i + self.width),
+            (i + self.width).checked_sub(1),
+            Some(i + self.width + 1),
+        ];
+        for candidate in candidates.iter().filter_map(|c| *c) {
+            if candidate < self.tiles.len() {
+                self.active.insert(candidate);
+            }
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn params(&self) -> &CaveParams {
+        &self.params
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        if self.completed {
+            Outcome::Completed
+        } else if !self.get_tile(self.player_pos).is_player() {
+            Outcome::Died
+        } else {
+            Outcome::Playing
         }
     }
 
+    /// The player gives up on the cave, which ends it like being killed
+    pub fn give_up(&mut self) {
+        if self.outcome() != Outcome::Playing {
+            return;
+        }
+        let pos = self.player_pos;
+        self.set_tile(pos, TileType::Empty);
+        self.events.push(GridEvent::Changed {
+            pos,
+            from: TileType::Player,
+            to: TileType::Empty,
+        });
+    }
+
+    /// Direction the player walked in during the last tick, if it did
+    pub fn player_walked(&self) -> Option<Direction> {
+        self.events.iter().find_map(|event| match *event {
+            GridEvent::Moved {
+                from,
+                to,
+                kind: TileType::Player,
+            } => {
+                let (from_x, from_y) = from.to_xy(self.width);
+                let (to_x, to_y) = to.to_xy(self.width);
+                Some(if to_x < from_x {
+                    Direction::Left
+                } else if to_x > from_x {
+                    Direction::Right
+                } else if to_y > from_y {
+                    Direction::Up
+                } else {
+                    Direction::Down
+                })
+            }
+            _ => None,
+        })
+    }
+
     pub fn height(&self) -> usize {
         self.height
     }
@@ -212,11 +479,24 @@ impl GridState {
         self.width
     }
 
+    /// All the tiles, row by row from the bottom
+    pub fn tiles(&self) -> &[TileType] {
+        &self.tiles
+    }
+
     pub fn get_tile(&self, pos: GridPos) -> TileType {
         *self.get_tile_ref(pos)
     }
 
+    /// The tile next to `pos`; past the edge of the map there is only
+    /// steel wall, reported at `pos` itself
     pub fn get_tile_relative(&self, pos: GridPos, direction: Direction) -> Tile {
+        if self.at_edge(pos, direction) {
+            return Tile {
+                pos,
+                kind: TileType::Steel,
+            };
+        }
         let dst_pos = pos.direction(direction, self.width);
         let dst_type = self.get_tile(dst_pos);
         Tile {
@@ -225,26 +505,66 @@ impl GridState {
         }
     }
 
+    fn at_edge(&self, pos: GridPos, direction: Direction) -> bool {
+        let (x, y) = pos.to_xy(self.width);
+        match direction {
+            Direction::Up => y + 1 >= self.height,
+            Direction::Down => y == 0,
+            Direction::Left => x == 0,
+            Direction::Right => x + 1 >= self.width,
+        }
+    }
+
     pub fn set_tile(&mut self, pos: GridPos, v: TileType) {
         *self.get_tile_mut(pos) = v;
+        self.wake_around(pos);
     }
 
     pub fn get_tile_ref(&self, pos: GridPos) -> &TileType {
         &self.tiles[pos.0]
     }
-    pub fn get_tile_mut(&mut self, pos: GridPos) -> &mut TileType {
+    fn get_tile_mut(&mut self, pos: GridPos) -> &mut TileType {
         &mut self.tiles[pos.0]
     }
 
     pub fn run_tick(&mut self, action: Vec<input::Action>) {
+        self.events.clear();
+        if self.outcome() != Outcome::Playing {
+            return;
+        }
+        self.tick_count += 1;
+        self.fell = std::mem::replace(&mut self.falling, CellSet::empty(self.tiles.len()));
+
         self.move_player(action);
 
-        for i in 0..self.tiles.len() {
+        // Same as visiting every cell in order, but skipping the sleeping ones.
+        // Cells woken up ahead of the current one still get their turn
+        // in this tick, just like in a full scan.
+        let mut next = 0;
+        while let Some(i) = self.active.next_from(next) {
+            next = i + 1;
             let pos = GridPos(i);
+            let tile = self.get_tile(pos);
+            // creatures change (at least their counter) every tick
+            if !tile.can_fall() && !tile.is_creature() {
+                self.active.remove(i);
+                continue;
+            }
+
             if let Some(new_tile) = self.run_tick_for_pos(pos) {
                 self.move_grid_object(pos, new_tile.pos);
                 // sometimes the tick will change the kind (i.e. creature direction / counter)
-                self.set_tile(new_tile.pos, new_tile.kind);
+                let old_kind = self.get_tile(new_tile.pos);
+                if old_kind != new_tile.kind {
+                    self.set_tile(new_tile.pos, new_tile.kind);
+                    self.events.push(GridEvent::Changed {
+                        pos: new_tile.pos,
+                        from: old_kind,
+                        to: new_tile.kind,
+                    });
+                }
+            } else if !tile.is_creature() {
+                self.active.remove(i);
             }
         }
     }
@@ -253,6 +573,14 @@ impl GridState {
     fn run_tick_for_pos(&mut self, current_pos: GridPos) -> Option<Tile> {
         let tile = self.get_tile(current_pos);
         if tile.can_fall() {
+            if let Some(new_pos) = self.try_crush(current_pos) {
+                self.falling.insert(new_pos.0);
+                return Some(Tile {
+                    pos: new_pos,
+                    kind: tile,
+                });
+            }
+
             if let Some(new_pos) = self.try_roll(current_pos) {
                 return Some(Tile {
                     pos: new_pos,
@@ -261,6 +589,7 @@ impl GridState {
             }
 
             if let Some(new_pos) = self.try_fall(current_pos) {
+                self.falling.insert(new_pos.0);
                 return Some(Tile {
                     pos: new_pos,
                     kind: tile,
@@ -268,16 +597,22 @@ impl GridState {
             }
         }
 
-        if let TileType::Creature { counter, direction } = tile {
+        if let TileType::Creature {
+            kind,
+            counter,
+            direction,
+        } = tile
+        {
             // counter just to slow down movement of the creatures
             if counter % 3 == 2 {
-                if let Some(new_tile) = self.try_creature_move(current_pos, direction) {
+                if let Some(new_tile) = self.try_creature_move(current_pos, kind, direction) {
                     return Some(new_tile);
                 }
             } else {
                 return Some(Tile {
                     pos: current_pos,
                     kind: TileType::Creature {
+                        kind,
                         counter: counter + 1,
                         direction: direction,
                     },
@@ -291,6 +626,10 @@ impl GridState {
     // move something from src_pos to dst_pos
     // anything at dst_pos will be destroyed
     fn move_grid_object(&mut self, src_pos: GridPos, dst_pos: GridPos) {
+        if src_pos == dst_pos {
+            return;
+        }
+
         let src_type = self.get_tile(src_pos);
         self.set_tile(src_pos, TileType::Empty);
         self.set_tile(dst_pos, src_type);
@@ -299,6 +638,12 @@ impl GridState {
             assert_eq!(src_type, TileType::Player);
             self.player_pos = dst_pos;
         }
+
+        self.events.push(GridEvent::Moved {
+            from: src_pos,
+            to: dst_pos,
+            kind: src_type,
+        });
     }
 
     fn move_player(&mut self, action: Vec<input::Action>) {
@@ -310,6 +655,15 @@ impl GridState {
             let dst = self.get_tile_relative(player_pos, action.direction);
             if dst.kind.is_diamond() {
                 self.diamond_count += 1;
+                self.events
+                    .push(GridEvent::DiamondCollected { pos: dst.pos });
+            }
+            if dst.kind.is_exit() {
+                if self.diamond_count < self.params.diamonds_needed {
+                    continue;
+                }
+                self.completed = true;
+                self.events.push(GridEvent::ExitEntered { pos: dst.pos });
             }
             if dst.kind.can_be_stepped_on() {
                 self.move_grid_object(player_pos, dst.pos);
@@ -342,9 +696,8 @@ impl GridState {
 
         match (left_free, right_free) {
             (true, true) => {
-                let mut rng = thread_rng();
                 let choices = [left.pos, right.pos];
-                Some(choices[rng.gen_range(0, choices.len())])
+                Some(choices[self.rng.gen_range(0, choices.len())])
             }
             (true, false) => Some(left.pos),
             (false, true) => Some(right.pos),
@@ -352,6 +705,24 @@ impl GridState {
         }
     }
 
+    // something that was falling and lands on a creature crushes it, and
+    // keeps falling into its place
+    fn try_crush(&mut self, current_pos: GridPos) -> Option<GridPos> {
+        let below = self.get_tile_relative(current_pos, Direction::Down);
+        if !below.kind.is_creature() || !self.fell.contains(current_pos.0) {
+            return None;
+        }
+
+        self.set_tile(below.pos, TileType::Empty);
+        self.creatures_killed += 1;
+        self.events.push(GridEvent::Changed {
+            pos: below.pos,
+            from: below.kind,
+            to: TileType::Empty,
+        });
+        Some(below.pos)
+    }
+
     fn try_fall(&mut self, current_pos: GridPos) -> Option<GridPos> {
         let below = self.get_tile_relative(current_pos, Direction::Down);
 
@@ -362,12 +733,18 @@ impl GridState {
         }
     }
 
-    fn try_creature_move(&mut self, current_pos: GridPos, direction: Direction) -> Option<Tile> {
+    fn try_creature_move(
+        &mut self,
+        current_pos: GridPos,
+        kind: CreatureKind,
+        direction: Direction,
+    ) -> Option<Tile> {
         let front = self.get_tile_relative(current_pos, direction);
         if front.kind.is_empty() {
             return Some(Tile {
                 pos: front.pos,
                 kind: TileType::Creature {
+                    kind,
                     counter: 0,
                     direction: direction,
                 },
@@ -403,6 +780,7 @@ impl GridState {
             return Some(Tile {
                 pos: new_tile.pos,
                 kind: TileType::Creature {
+                    kind,
                     counter: 0,
                     direction: new_direction,
                 },
@@ -414,6 +792,7 @@ impl GridState {
             TileType::Empty => Some(Tile {
                 pos: opposite.pos,
                 kind: TileType::Creature {
+                    kind,
                     counter: 0,
                     direction: direction.opposite(),
                 },
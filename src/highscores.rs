@@ -0,0 +1,93 @@
+//! The best scores made on this computer, with the names of who made them
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Entries kept in each table
+pub const TABLE_SIZE: usize = 10;
+
+/// Which table a score goes into
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TableKey {
+    pub pack: String,
+    pub cave: String,
+    pub level: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub score: usize,
+    pub ticks: u64,
+    pub diamonds: usize,
+}
+
+/// Tables of the best scores, best first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    pub tables: BTreeMap<TableKey, Vec<Entry>>,
+    // offered again the next time a name is asked for
+    #[serde(default)]
+    pub last_name: Option<String>,
+}
+
+/// Where the high scores of this user are kept
+pub fn user_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("bd").join("highscores.ron"))
+}
+
+impl HighScores {
+    /// High scores from `path`; none made yet if the file isn't there
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(HighScores::default());
+        }
+        Ok(ron::de::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let pretty = ron::ser::PrettyConfig::default();
+        fs::write(path, ron::ser::to_string_pretty(self, pretty)?)?;
+        Ok(())
+    }
+
+    pub fn table(&self, key: &TableKey) -> &[Entry] {
+        self.tables.get(key).map_or(&[], |table| &table[..])
+    }
+
+    /// Whether `score` would make it into the table
+    pub fn qualifies(&self, key: &TableKey, score: usize) -> bool {
+        let table = self.table(key);
+        score > 0 && (table.len() < TABLE_SIZE || table.iter().any(|e| e.score < score))
+    }
+
+    /// Put an entry in its place in the table, and give that place, if it
+    /// made it; with equal scores the faster one comes first, then the older
+    pub fn insert(&mut self, key: TableKey, entry: Entry) -> Option<usize> {
+        if !self.qualifies(&key, entry.score) {
+            return None;
+        }
+        self.last_name = Some(entry.name.clone());
+
+        let table = self.tables.entry(key).or_default();
+        let place = table
+            .iter()
+            .position(|e| {
+                (e.score, std::cmp::Reverse(e.ticks))
+                    < (entry.score, std::cmp::Reverse(entry.ticks))
+            })
+            .unwrap_or(table.len());
+        table.insert(place, entry);
+        table.truncate(TABLE_SIZE);
+        Some(place)
+    }
+}
@@ -1,5 +1,10 @@
-use amethyst::input::{ElementState, VirtualKeyCode};
-use std::collections::{HashSet, VecDeque};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashSet, VecDeque},
+    fs,
+    path::Path,
+};
 
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
 pub enum Direction {
@@ -28,31 +33,30 @@ pub enum Button {
 }
 
 impl Button {
-    fn from_key(key: VirtualKeyCode) -> Option<Self> {
+    /// Button of a named action of `config/bindings.ron`
+    pub fn from_action(action: &str) -> Option<Self> {
         use Button::*;
         use Direction::*;
-        Some(match key {
-            VirtualKeyCode::Left => Move(Left),
-            VirtualKeyCode::Right => Move(Right),
-            VirtualKeyCode::Up => Move(Up),
-            VirtualKeyCode::Down => Move(Down),
-            VirtualKeyCode::RControl => Fire,
+        Some(match action {
+            "left" => Move(Left),
+            "right" => Move(Right),
+            "up" => Move(Up),
+            "down" => Move(Down),
+            "fire" => Fire,
             _ => return None,
         })
     }
+}
 
-    #[allow(unused)]
-    fn to_key(self) -> VirtualKeyCode {
-        use Button::*;
-        use Direction::*;
-        match self {
-            Move(Up) => VirtualKeyCode::Up,
-            Move(Down) => VirtualKeyCode::Down,
-            Move(Left) => VirtualKeyCode::Left,
-            Move(Right) => VirtualKeyCode::Right,
-            Fire => VirtualKeyCode::LControl,
-        }
-    }
+/// Analog stick positions closer to the center than this don't move
+pub const STICK_DEADZONE: f32 = 0.3;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StickAxis {
+    // positive is right
+    Horizontal,
+    // positive is up
+    Vertical,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -61,42 +65,138 @@ pub struct Action {
     pub direction: Direction,
 }
 
+/// How presses and releases of the direction keys turn into the actions of
+/// a tick. Of the actions of a tick, the player takes the first one it can.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BufferMode {
+    /// Like the original: every press moves at least once, even if released
+    /// before the tick, and while keys are held the one pressed last wins,
+    /// falling back to the one held before it
+    LastPressedWins,
+    /// Only presses move, one per tick, in the order they came; holding
+    /// a key doesn't repeat
+    QueuedTaps,
+    /// At most one action a tick: the oldest press not moved yet, or else
+    /// the key held that was pressed last
+    OneActionPerTick,
+}
+
+impl Default for BufferMode {
+    fn default() -> Self {
+        BufferMode::LastPressedWins
+    }
+}
+
+pub const INPUT_CONFIG: &str = "./config/input.ron";
+
+/// Settings of the input, from `config/input.ron`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InputConfig {
+    #[serde(default)]
+    pub buffer_mode: BufferMode,
+}
+
+impl InputConfig {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(ron::de::from_str(&fs::read_to_string(path)?)?)
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct InputTracker {
+    pub mode: BufferMode,
     pub actions_pending: VecDeque<Action>,
     pub keys_down: HashSet<Button>,
     pub movements_down: VecDeque<Direction>,
+    stick: (f32, f32),
+    stick_direction: Option<Direction>,
 }
 
 impl InputTracker {
+    pub fn new(mode: BufferMode) -> Self {
+        InputTracker {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    /// Forget all the keys held and presses not moved yet, like when the
+    /// game didn't get to see them getting released
+    pub fn release_all(&mut self) {
+        *self = InputTracker::new(self.mode);
+    }
+
+    /// Actions for the next tick, in the order the player should try them
     pub fn pop_action(&mut self) -> Vec<Action> {
-        self.actions_pending
-            .pop_front()
-            .into_iter()
-            .chain(
-                self.movements_down
-                    .iter()
-                    .take(2)
-                    .copied()
-                    .map(|direction| Action {
-                        direction,
-                        fire: self.keys_down.contains(&Button::Fire),
-                    }),
-            )
-            .collect()
+        let fire = self.keys_down.contains(&Button::Fire);
+        let pressed = self.actions_pending.pop_front();
+        let held = self
+            .movements_down
+            .iter()
+            .copied()
+            .map(|direction| Action { direction, fire });
+
+        match self.mode {
+            BufferMode::LastPressedWins => pressed.into_iter().chain(held.take(2)).collect(),
+            BufferMode::QueuedTaps => pressed.into_iter().collect(),
+            BufferMode::OneActionPerTick => pressed.into_iter().chain(held).take(1).collect(),
+        }
     }
 
-    pub fn handle_key(&mut self, (key, key_state): (VirtualKeyCode, ElementState)) {
-        let button = if let Some(button) = Button::from_key(key) {
-            button
+    /// A key press from a source that never reports releases, like a
+    /// terminal: one move, with at most one more waiting, so auto-repeat
+    /// doesn't pile up moves after the key is let go
+    pub fn press(&mut self, direction: Direction, fire: bool) {
+        if self.actions_pending.len() < 2 {
+            self.actions_pending.push_back(Action { fire, direction });
+        }
+    }
+
+    /// A named action of `config/bindings.ron` got pressed or released
+    pub fn handle_action(&mut self, action: &str, pressed: bool) {
+        if let Some(button) = Button::from_action(action) {
+            self.handle_button(button, pressed);
+        }
+    }
+
+    /// An analog stick axis moved to `value`, between -1 and 1; the stick
+    /// acts like the direction button it leans towards the most
+    pub fn handle_stick(&mut self, axis: StickAxis, value: f32) {
+        match axis {
+            StickAxis::Horizontal => self.stick.0 = value,
+            StickAxis::Vertical => self.stick.1 = value,
+        }
+
+        let (x, y) = self.stick;
+        let direction = if x.abs().max(y.abs()) < STICK_DEADZONE {
+            None
+        } else if x.abs() > y.abs() {
+            Some(if x > 0. {
+                Direction::Right
+            } else {
+                Direction::Left
+            })
         } else {
-            return;
+            Some(if y > 0. {
+                Direction::Up
+            } else {
+                Direction::Down
+            })
         };
 
-        match (
-            self.keys_down.contains(&button),
-            key_state == ElementState::Pressed,
-        ) {
+        if direction != self.stick_direction {
+            if let Some(old) = self.stick_direction {
+                self.handle_button(Button::Move(old), false);
+            }
+            if let Some(new) = direction {
+                self.handle_button(Button::Move(new), true);
+            }
+            self.stick_direction = direction;
+        }
+    }
+
+    pub fn handle_button(&mut self, button: Button, pressed: bool) {
+        match (self.keys_down.contains(&button), pressed) {
             (true, true) => {}
             (false, false) => {}
             (true, false) => {
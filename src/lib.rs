@@ -0,0 +1,12 @@
+//! Game rules and data, independent of the window and the GPU
+pub mod game;
+pub mod grid;
+pub mod highscores;
+pub mod input;
+pub mod map;
+pub mod palette;
+pub mod records;
+pub mod render;
+pub mod replay;
+pub mod session;
+pub mod theme;
@@ -10,28 +10,85 @@ use amethyst::{
     ui::{RenderUi, UiBundle},
     utils::application_root_dir,
 };
+use std::time::Duration;
 
+mod bindings;
 pub mod system;
 
+pub use bd::{
+    game, grid, highscores, input, map, palette, records, render, replay, session, theme,
+};
 pub use system::*;
-mod grid;
-mod input;
-mod map;
+mod export;
 mod state;
+mod tui;
+mod verify;
 
 const TILE_SIZE: f32 = 32.;
 
 fn main() -> amethyst::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("--verify") {
+        if args.len() != 4 {
+            eprintln!("Usage: {} --verify <map> <replay>", args[0]);
+            std::process::exit(2);
+        }
+        if let Err(e) = verify::run(args[2].clone().into(), args[3].clone().into()) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if args.get(1).map(String::as_str) == Some("--render") {
+        if args.len() != 4 && args.len() != 5 {
+            eprintln!("Usage: {} --render <map> <output> [replay]", args[0]);
+            std::process::exit(2);
+        }
+        let replay = args.get(4).map(Into::into);
+        if let Err(e) = export::png(args[2].clone().into(), args[3].clone().into(), replay) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if args.get(1).map(String::as_str) == Some("--gif") {
+        if args.len() != 5 {
+            eprintln!("Usage: {} --gif <map> <replay> <output.gif>", args[0]);
+            std::process::exit(2);
+        }
+        if let Err(e) = export::gif(
+            args[2].clone().into(),
+            args[3].clone().into(),
+            args[4].clone().into(),
+        ) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if args.get(1).map(String::as_str) == Some("--tui") {
+        if args.len() > 3 {
+            eprintln!("Usage: {} --tui [map]", args[0]);
+            std::process::exit(2);
+        }
+        if let Err(e) = tui::run(args.get(2).map(Into::into)) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     amethyst::start_logger(Default::default());
 
     let app_root = application_root_dir()?;
 
     let resources = app_root.join("resources");
     let display_config = resources.join("display_config.ron");
-    let binding_path = app_root.join("config").join("bindings.ron");
+    let binding_path = app_root.join(bindings::DEFAULT_BINDINGS);
 
-    let input_bundle =
-        InputBundle::<StringBindings>::new().with_bindings_from_file(binding_path)?;
+    let bindings = bindings::load(&binding_path)
+        .map_err(|e| amethyst::Error::from_string(format!("Failed to load bindings: {}", e)))?;
+    let input_bundle = InputBundle::<StringBindings>::new().with_bindings(bindings);
 
     let game_data = GameDataBuilder::default()
         .with_bundle(TransformBundle::new())?
@@ -46,7 +103,11 @@ fn main() -> amethyst::Result<()> {
                 .with_plugin(RenderUi::default()),
         )?;
 
-    let mut game = Application::new(resources, state::MainScreen::default(), game_data)?;
+    let mut game = Application::build(resources, state::MainScreen::default())?
+        .with_fixed_step_length(Duration::from_nanos(
+            1_000_000_000 / game::FIXED_UPDATES_PER_SECOND,
+        ))
+        .build(game_data)?;
     game.run();
 
     Ok(())
@@ -2,36 +2,112 @@ use anyhow::{bail, format_err, Result};
 use std::{
     fs::File,
     io::{BufRead, BufReader},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use crate::{
-    grid::{GridPos, TileType},
+    grid::{CreatureKind, GridPos, TileType},
     input::Direction,
+    palette::{self, Palette},
 };
 
+/// Properties of a cave, from the `key: value` lines at the top of the map file
 #[derive(Debug, Clone)]
+pub struct CaveParams {
+    pub name: Option<String>,
+    // the exit stays shut until this many are collected
+    pub diamonds_needed: usize,
+    // seconds to complete the cave in, if there's a limit
+    pub time: Option<u64>,
+    // points for each diamond, and for each one past the ones needed
+    pub diamond_value: usize,
+    pub extra_diamond_value: usize,
+    pub creature_value: usize,
+    // points for each second left when the cave is completed
+    pub time_bonus: usize,
+    pub palette: Option<Palette>,
+}
+
+impl Default for CaveParams {
+    fn default() -> Self {
+        CaveParams {
+            name: None,
+            diamonds_needed: 0,
+            time: None,
+            diamond_value: 10,
+            extra_diamond_value: 15,
+            creature_value: 0,
+            time_bonus: 1,
+            palette: None,
+        }
+    }
+}
+
+impl CaveParams {
+    fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "name" => self.name = Some(value.to_string()),
+            "diamonds_needed" => self.diamonds_needed = value.parse()?,
+            "time" => self.time = Some(value.parse()?),
+            "diamond_value" => self.diamond_value = value.parse()?,
+            "extra_diamond_value" => self.extra_diamond_value = value.parse()?,
+            "creature_value" => self.creature_value = value.parse()?,
+            "time_bonus" => self.time_bonus = value.parse()?,
+            "dirt_color" => self.palette_mut().dirt = Some(palette::parse_color(value)?),
+            "wall_color" => self.palette_mut().wall = Some(palette::parse_color(value)?),
+            "foreground_color" => {
+                self.palette_mut().foreground = Some(palette::parse_color(value)?)
+            }
+            _ => bail!("Unknown map property: {}", key),
+        }
+        Ok(())
+    }
+
+    fn palette_mut(&mut self) -> &mut Palette {
+        self.palette.get_or_insert_with(Palette::default)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct MapDescription {
     pub tiles: Vec<TileType>,
     pub width: usize,
     pub height: usize,
     pub start: GridPos,
+    pub params: CaveParams,
 }
 
 impl MapDescription {
     pub fn load(path: PathBuf) -> Result<Self> {
+        let file = File::open(path)?;
+        Self::from_reader(BufReader::new(file))
+    }
+
+    pub fn from_reader(reader: impl BufRead) -> Result<Self> {
         let mut start = None;
 
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
         let lines: Result<Vec<String>> = reader.lines().map(|e| e.map_err(|e| e.into())).collect();
-        let lines = lines?;
-        let width = lines[0].len();
+        let mut lines = lines?;
+
+        let mut params = CaveParams::default();
+        let header_len = lines.iter().take_while(|line| line.contains(':')).count();
+        for line in lines.drain(..header_len) {
+            let mut split = line.splitn(2, ':');
+            let key = split.next().unwrap_or("").trim();
+            let value = split.next().unwrap_or("").trim();
+            params.set(key, value)?;
+        }
+
+        let width = lines
+            .first()
+            .ok_or_else(|| format_err!("Empty map"))?
+            .chars()
+            .count();
         let height = lines.len();
         let mut tiles = Vec::with_capacity(width * height);
 
         for (y, line) in lines.iter().rev().enumerate() {
-            if width != line.len() {
+            if width != line.chars().count() {
                 bail!("Lines not equal len");
             }
 
@@ -49,7 +125,14 @@ impl MapDescription {
                     '.' => TileType::Dirt,
                     'o' => TileType::Rock,
                     '*' => TileType::Diamond,
+                    'X' => TileType::Exit,
                     'x' => TileType::Creature {
+                        kind: CreatureKind::Firefly,
+                        counter: 0,
+                        direction: Direction::Down,
+                    },
+                    'b' => TileType::Creature {
+                        kind: CreatureKind::Butterfly,
                         counter: 0,
                         direction: Direction::Down,
                     },
@@ -63,6 +146,61 @@ impl MapDescription {
             width,
             height,
             start: start.ok_or_else(|| format_err!("No start position found"))?,
+            params,
         })
     }
 }
+
+/// Where the bundled caves are
+pub const MAPS_DIR: &str = "./resources/map";
+
+/// The map files of a directory, in the order they are played
+pub fn list(dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().map_or(false, |ext| ext == "txt") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// A map, and the id its results are kept under: the file name
+#[derive(Debug, Clone)]
+pub struct Cave {
+    pub id: String,
+    // the map file it was loaded from
+    pub path: PathBuf,
+    // the directory the cave came with
+    pub pack: String,
+    pub map: MapDescription,
+}
+
+impl Cave {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let id = path
+            .file_stem()
+            .ok_or_else(|| format_err!("Not a map file: {}", path.display()))?
+            .to_string_lossy()
+            .into_owned();
+        let pack = path
+            .parent()
+            .and_then(Path::file_name)
+            .map_or(String::new(), |name| name.to_string_lossy().into_owned());
+
+        Ok(Cave {
+            id,
+            path: path.to_path_buf(),
+            pack,
+            map: MapDescription::load(path.to_path_buf())?,
+        })
+    }
+
+    /// The name from the map header, or else the id
+    pub fn name(&self) -> &str {
+        self.map.params.name.as_ref().unwrap_or(&self.id)
+    }
+}
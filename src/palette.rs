@@ -0,0 +1,89 @@
+use anyhow::{bail, Result};
+
+use crate::grid::TileType;
+
+pub type Rgb = [u8; 3];
+
+/// Colours of a cave, like every cave of the original had its own; the
+/// sprites of the colours left out keep the colours of the sprite sheet
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Palette {
+    pub dirt: Option<Rgb>,
+    pub wall: Option<Rgb>,
+    // rocks and steel wall
+    pub foreground: Option<Rgb>,
+}
+
+impl Palette {
+    /// Colour the sprite of `tile` should be painted with, if any
+    pub fn color_for(&self, tile: TileType) -> Option<Rgb> {
+        use TileType::*;
+        match tile {
+            Dirt => self.dirt,
+            Wall => self.wall,
+            Rock | Steel | Exit => self.foreground,
+            _ => None,
+        }
+    }
+}
+
+/// Parse `#rrggbb`
+pub fn parse_color(s: &str) -> Result<Rgb> {
+    let hex = s.trim();
+    if hex.len() != 7 || !hex.starts_with('#') || !hex.is_ascii() {
+        bail!("Color must look like #rrggbb, got: {}", s);
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+
+    Ok([channel(1)?, channel(3)?, channel(5)?])
+}
+
+/// Rectangle of a sprite in the sprite sheet, in pixels
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SpriteRect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Paint the sprite at `rect` with `color`, keeping its shading
+///
+/// `pixels` is an RGBA image, `image_width` pixels wide. The brightest
+/// pixel of the sprite becomes exactly `color`, black stays black,
+/// and transparency is left alone.
+pub fn recolor(pixels: &mut [u8], image_width: usize, rect: SpriteRect, color: Rgb) {
+    let image_height = pixels.len() / 4 / image_width.max(1);
+    let xs = rect.x.min(image_width)..(rect.x + rect.width).min(image_width);
+    let ys = rect.y.min(image_height)..(rect.y + rect.height).min(image_height);
+    let offset = |x: usize, y: usize| (y * image_width + x) * 4;
+    let luminance =
+        |p: &[u8]| 299 * u32::from(p[0]) + 587 * u32::from(p[1]) + 114 * u32::from(p[2]);
+
+    let mut max = 0;
+    for y in ys.clone() {
+        for x in xs.clone() {
+            let p = &pixels[offset(x, y)..offset(x, y) + 4];
+            if p[3] != 0 {
+                max = max.max(luminance(p));
+            }
+        }
+    }
+
+    for y in ys {
+        for x in xs.clone() {
+            let p = &mut pixels[offset(x, y)..offset(x, y) + 4];
+            if p[3] == 0 {
+                continue;
+            }
+            let l = luminance(p);
+            for (channel, target) in p.iter_mut().zip(color.iter()) {
+                *channel = if max == 0 {
+                    0
+                } else {
+                    (u32::from(*target) * l / max) as u8
+                };
+            }
+        }
+    }
+}
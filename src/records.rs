@@ -0,0 +1,76 @@
+//! The best results of every cave, kept between games
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::highscores::TableKey;
+
+/// Best results of one cave
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaveRecord {
+    pub best_score: Option<usize>,
+    // ticks of the fastest completion
+    pub best_ticks: Option<u64>,
+    pub completed: bool,
+}
+
+/// Best results of the caves, kept apart by pack and level like the high
+/// scores
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Records {
+    pub caves: BTreeMap<TableKey, CaveRecord>,
+}
+
+/// Where the records of this user are kept
+pub fn user_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("bd").join("records.ron"))
+}
+
+impl Records {
+    /// Records from `path`; nothing recorded yet if the file isn't there
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Records::default());
+        }
+        Ok(ron::de::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let pretty = ron::ser::PrettyConfig::default();
+        fs::write(path, ron::ser::to_string_pretty(self, pretty)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, key: &TableKey) -> CaveRecord {
+        self.caves.get(key).cloned().unwrap_or_default()
+    }
+
+    /// Keep the results of a finished game of a cave where they beat the
+    /// ones before; the time only counts if the cave was completed
+    pub fn record(&mut self, key: TableKey, score: usize, ticks: u64, completed: bool) {
+        let record = self.caves.entry(key).or_default();
+        record.best_score = record.best_score.max(Some(score));
+        if completed {
+            record.completed = true;
+            record.best_ticks = Some(record.best_ticks.map_or(ticks, |best| best.min(ticks)));
+        }
+    }
+
+    /// The first cave is always open, the others once the one before
+    /// them is completed
+    pub fn unlocked(&self, caves: &[TableKey], i: usize) -> bool {
+        i == 0
+            || caves
+                .get(i - 1)
+                .map_or(false, |cave| self.get(cave).completed)
+    }
+}
@@ -0,0 +1,222 @@
+//! Drawing grids into images on the CPU, with no window or GPU
+use anyhow::{format_err, Result};
+use gif::SetParameter;
+use image::{Rgba, RgbaImage};
+use std::{collections::HashMap, convert::TryFrom, io::Write, time::Duration};
+
+use crate::{
+    grid::{GridPos, GridState},
+    palette::{self, Palette, SpriteRect},
+    theme::{self, Theme},
+};
+
+/// Draws grids with the sprites of a theme, the way the game shows them
+pub struct Renderer {
+    sheet: RgbaImage,
+    rects: Vec<SpriteRect>,
+    theme: Theme,
+    tile_size: u32,
+}
+
+impl Renderer {
+    /// Renderer of the named theme, with the sprites painted in `palette`
+    /// like the game does
+    pub fn load(theme_name: &str, palette: Option<Palette>) -> Result<Self> {
+        let theme = Theme::load(theme_name)?;
+        let mut sheet = image::open(theme::dir(theme_name).join("grid.png"))?.to_rgba();
+        if let Some(palette) = palette {
+            let width = sheet.width() as usize;
+            for (rect, color) in theme.palette_regions(theme_name, palette)? {
+                palette::recolor(&mut sheet, width, rect, color);
+            }
+        }
+
+        Ok(Self::new(sheet, theme::sprite_rects(theme_name)?, theme))
+    }
+
+    pub fn new(sheet: RgbaImage, rects: Vec<SpriteRect>, theme: Theme) -> Self {
+        let tile_size = theme::tile_size(&rects) as u32;
+
+        Renderer {
+            sheet,
+            rects,
+            theme,
+            tile_size,
+        }
+    }
+
+    /// Size of a grid cell in the image, in pixels
+    pub fn tile_size(&self) -> u32 {
+        self.tile_size
+    }
+
+    /// The whole grid, `time` seconds into the animations
+    pub fn render(&self, grid: &GridState, time: f64) -> RgbaImage {
+        let size = self.tile_size;
+        let mut image = RgbaImage::from_pixel(
+            grid.width() as u32 * size,
+            grid.height() as u32 * size,
+            Rgba([0, 0, 0, 255]),
+        );
+
+        let walked = grid.player_walked();
+        for (i, tile) in grid.tiles().iter().enumerate() {
+            let sprite = match self.theme.animation_for(*tile, walked) {
+                Some(animation) => self.theme.frame_at(animation, time),
+                None => self.theme.sprite_for(*tile),
+            };
+            let rect = match sprite.and_then(|sprite| self.rects.get(sprite)) {
+                Some(rect) => rect,
+                None => continue,
+            };
+
+            // the grid has y going up, images down
+            let (x, y) = GridPos::new(i).to_xy(grid.width());
+            let top = (grid.height() - 1 - y) as u32 * size;
+            self.draw_sprite(&mut image, *rect, x as u32 * size, top);
+        }
+
+        image
+    }
+
+    /// The whole grid at the start of the animations, scaled down to fit
+    /// in `width` by `height` pixels, keeping its proportions
+    pub fn preview(&self, grid: &GridState, width: u32, height: u32) -> RgbaImage {
+        let image = self.render(grid, 0.);
+        let scale = (width as f32 / image.width() as f32)
+            .min(height as f32 / image.height() as f32)
+            .min(1.);
+        let scaled = |size: u32| ((size as f32 * scale) as u32).max(1);
+        image::imageops::thumbnail(&image, scaled(image.width()), scaled(image.height()))
+    }
+
+    fn draw_sprite(&self, image: &mut RgbaImage, rect: SpriteRect, left: u32, top: u32) {
+        for dy in 0..rect.height as u32 {
+            for dx in 0..rect.width as u32 {
+                let (x, y) = (rect.x as u32 + dx, rect.y as u32 + dy);
+                if x >= self.sheet.width() || y >= self.sheet.height() {
+                    continue;
+                }
+                let src = self.sheet.get_pixel(x, y);
+                let dst = image.get_pixel_mut(left + dx, top + dy);
+                let alpha = u32::from(src[3]);
+                for channel in 0..3 {
+                    let blended =
+                        u32::from(src[channel]) * alpha + u32::from(dst[channel]) * (255 - alpha);
+                    dst[channel] = (blended / 255) as u8;
+                }
+            }
+        }
+    }
+}
+
+/// Writes images of the same size as frames of an endlessly looping
+/// animated GIF
+pub struct GifWriter<W: Write> {
+    encoder: gif::Encoder<W>,
+    width: u32,
+    height: u32,
+    previous: Option<RgbaImage>,
+    // how long the frames written so far are shown
+    shown: Duration,
+}
+
+impl<W: Write> GifWriter<W> {
+    pub fn new(writer: W, width: u32, height: u32) -> Result<Self> {
+        let mut encoder = gif::Encoder::new(writer, gif_size(width)?, gif_size(height)?, &[])?;
+        encoder.set(gif::Repeat::Infinite)?;
+
+        Ok(GifWriter {
+            encoder,
+            width,
+            height,
+            previous: None,
+            shown: Duration::default(),
+        })
+    }
+
+    /// Add a frame shown for `duration`; only the part that changed since
+    /// the previous frame gets stored
+    pub fn add_frame(&mut self, image: &RgbaImage, duration: Duration) -> Result<()> {
+        if image.dimensions() != (self.width, self.height) {
+            return Err(format_err!(
+                "{}x{} frame doesn't fit a {}x{} GIF",
+                image.width(),
+                image.height(),
+                self.width,
+                self.height
+            ));
+        }
+        let (left, top, width, height) = match &self.previous {
+            // a frame can't be empty, so a pixel it is
+            Some(previous) => changed_area(previous, image).unwrap_or((0, 0, 1, 1)),
+            None => (0, 0, image.width(), image.height()),
+        };
+
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for y in top..top + height {
+            for x in left..left + width {
+                pixels.extend_from_slice(&image.get_pixel(x, y).data);
+            }
+        }
+
+        let mut frame = indexed_frame(width as u16, height as u16, &mut pixels);
+        frame.left = left as u16;
+        frame.top = top as u16;
+        frame.dispose = gif::DisposalMethod::Keep;
+        // GIF delays are in hundredths of a second, so round the total time
+        // instead of every frame to not drift away from `duration`
+        let hundredths = |time: Duration| (time.as_secs_f64() * 100.).round() as u64;
+        frame.delay = (hundredths(self.shown + duration) - hundredths(self.shown)) as u16;
+        self.shown += duration;
+
+        self.encoder.write_frame(&frame)?;
+        self.previous = Some(image.clone());
+        Ok(())
+    }
+}
+
+// GIF sizes and positions are 16 bits
+fn gif_size(pixels: u32) -> Result<u16> {
+    u16::try_from(pixels).map_err(|_| format_err!("{} pixels is too big for a GIF", pixels))
+}
+
+// left, top, width and height of the smallest rectangle with all the
+// pixels that differ between the images, which are of the same size
+fn changed_area(a: &RgbaImage, b: &RgbaImage) -> Option<(u32, u32, u32, u32)> {
+    let mut area: Option<(u32, u32, u32, u32)> = None;
+    for (x, y, pixel) in b.enumerate_pixels() {
+        if a.get_pixel(x, y) == pixel {
+            continue;
+        }
+        area = Some(match area {
+            None => (x, y, x, y),
+            Some((min_x, min_y, max_x, max_y)) => {
+                (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+            }
+        });
+    }
+    area.map(|(min_x, min_y, max_x, max_y)| (min_x, min_y, max_x - min_x + 1, max_y - min_y + 1))
+}
+
+// the sprites use only a handful of colours, so they usually fit into the
+// palette of a frame exactly; if not, they get quantized
+fn indexed_frame(width: u16, height: u16, pixels: &mut [u8]) -> gif::Frame<'static> {
+    let mut palette = vec![];
+    let mut indices = HashMap::new();
+    let mut indexed = Vec::with_capacity(pixels.len() / 4);
+    for pixel in pixels.chunks(4) {
+        let rgb = [pixel[0], pixel[1], pixel[2]];
+        let next = indices.len();
+        let index = *indices.entry(rgb).or_insert(next);
+        if index == 256 {
+            return gif::Frame::from_rgba_speed(width, height, pixels, 10);
+        }
+        if index == next {
+            palette.extend_from_slice(&rgb);
+        }
+        indexed.push(index as u8);
+    }
+
+    gif::Frame::from_palette_pixels(width, height, &indexed, &palette, None)
+}
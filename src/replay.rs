@@ -0,0 +1,116 @@
+use anyhow::{bail, format_err, Result};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::PathBuf,
+};
+
+use crate::input::{Action, Direction};
+
+/// Everything needed to re-run a game: the seed of the grid and
+/// the actions fed into every tick
+///
+/// On disk the first line is `seed <number>`, optionally followed by a
+/// `cave <map file>` line, and every next line is one tick, with actions
+/// separated by spaces. Each action is one of `u`, `d`, `l`, `r`; uppercase
+/// means fire was held.
+#[derive(Debug, Clone, Default)]
+pub struct Replay {
+    pub seed: u64,
+    // map file of the cave played, if known
+    pub cave: Option<PathBuf>,
+    pub ticks: Vec<Vec<Action>>,
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        Replay {
+            seed,
+            cave: None,
+            ticks: vec![],
+        }
+    }
+
+    pub fn record(&mut self, actions: &[Action]) {
+        self.ticks.push(actions.to_vec());
+    }
+
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        let mut lines = reader.lines().peekable();
+
+        let first = lines
+            .next()
+            .ok_or_else(|| format_err!("Empty replay file"))??;
+        let seed = match first.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["seed", seed] => seed.parse()?,
+            _ => bail!("Replay must start with a seed line"),
+        };
+
+        let mut cave = None;
+        if let Some(Ok(line)) = lines.peek() {
+            if let Some(path) = line.strip_prefix("cave ") {
+                cave = Some(PathBuf::from(path));
+                lines.next();
+            }
+        }
+
+        let mut ticks = vec![];
+        for line in lines {
+            let line = line?;
+            let mut actions = vec![];
+            for token in line.split_whitespace() {
+                actions.push(parse_action(token)?);
+            }
+            ticks.push(actions);
+        }
+
+        Ok(Replay { seed, cave, ticks })
+    }
+
+    pub fn save(&self, path: PathBuf) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "seed {}", self.seed)?;
+        if let Some(cave) = &self.cave {
+            writeln!(writer, "cave {}", cave.display())?;
+        }
+        for actions in &self.ticks {
+            let tokens: Vec<_> = actions.iter().map(|a| format_action(*a)).collect();
+            writeln!(writer, "{}", tokens.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_action(token: &str) -> Result<Action> {
+    use Direction::*;
+    let direction = match token.to_ascii_lowercase().as_str() {
+        "u" => Up,
+        "d" => Down,
+        "l" => Left,
+        "r" => Right,
+        _ => bail!("Unknown action in replay: {}", token),
+    };
+
+    Ok(Action {
+        fire: token.chars().all(|ch| ch.is_ascii_uppercase()),
+        direction,
+    })
+}
+
+fn format_action(action: Action) -> String {
+    use Direction::*;
+    let s = match action.direction {
+        Up => "u",
+        Down => "d",
+        Left => "l",
+        Right => "r",
+    };
+
+    if action.fire {
+        s.to_ascii_uppercase()
+    } else {
+        s.to_string()
+    }
+}
@@ -0,0 +1,88 @@
+//! A game of several caves in a row, played on a few lives
+
+/// Lives at the start of a game
+pub const START_LIVES: u32 = 3;
+/// Another life every time the score goes past a multiple of this
+pub const EXTRA_LIFE_POINTS: usize = 500;
+/// Going through all the caves starts them over on the next level, up to this
+pub const MAX_LEVEL: u32 = 5;
+
+/// How a cave of the game ended
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CaveEnd {
+    Completed,
+    Died,
+    OutOfTime,
+    // the player left the game
+    Quit,
+}
+
+/// How a cave ended, and the points scored in it
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CaveResult {
+    pub end: CaveEnd,
+    pub score: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub lives: u32,
+    // total of all the caves played
+    pub score: usize,
+    // index of the cave being played, out of `caves`
+    pub cave: usize,
+    pub caves: usize,
+    pub level: u32,
+}
+
+impl Session {
+    pub fn new(caves: usize) -> Self {
+        Session {
+            lives: START_LIVES,
+            score: 0,
+            cave: 0,
+            caves,
+            level: 1,
+        }
+    }
+
+    /// Like "Cave B, Level 1"
+    pub fn intro(&self) -> String {
+        format!("Cave {}, Level {}", cave_letter(self.cave), self.level)
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.lives == 0
+    }
+
+    /// Count the points of a cave, and move on to the next one if it was
+    /// completed, or else lose a life
+    pub fn cave_over(&mut self, result: CaveResult) {
+        if result.end == CaveEnd::Quit {
+            return;
+        }
+        self.add_score(result.score);
+
+        if result.end == CaveEnd::Completed {
+            self.cave += 1;
+            if self.cave >= self.caves {
+                self.cave = 0;
+                self.level = (self.level + 1).min(MAX_LEVEL);
+            }
+        } else {
+            self.lives = self.lives.saturating_sub(1);
+        }
+    }
+
+    fn add_score(&mut self, points: usize) {
+        let extra_lives =
+            (self.score + points) / EXTRA_LIFE_POINTS - self.score / EXTRA_LIFE_POINTS;
+        self.lives += extra_lives as u32;
+        self.score += points;
+    }
+}
+
+/// Caves are lettered from A on, like the original
+pub fn cave_letter(cave: usize) -> char {
+    (b'A' + (cave % 26) as u8) as char
+}
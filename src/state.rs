@@ -1,28 +1,73 @@
+mod game_session;
+mod high_score_table;
+mod interlude;
+mod level_select;
 mod main_screen;
+mod menu;
+mod name_entry;
+mod pause;
 mod playing_map;
+mod settings;
 
 use amethyst::{
-    assets::{AssetStorage, Handle, Loader},
+    assets::{AssetStorage, Format, Handle, Loader},
     ecs::prelude::*,
-    renderer::{ImageFormat, SpriteSheet, SpriteSheetFormat, Texture},
+    renderer::{types::TextureData, ImageFormat, SpriteSheet, SpriteSheetFormat, Texture},
 };
 
+use crate::{
+    map::{self, Cave},
+    palette::{self, Palette, Rgb, SpriteRect},
+    theme::Theme,
+};
+
+pub use game_session::*;
+pub use high_score_table::*;
+pub use interlude::*;
+pub use level_select::*;
 pub use main_screen::*;
+pub use name_entry::*;
+pub use pause::*;
 pub use playing_map::*;
+pub use settings::*;
+
+// the caves of `resources/map` in order, leaving out any that don't load
+fn load_caves() -> Vec<Cave> {
+    let paths = map::list(map::MAPS_DIR).unwrap_or_else(|e| {
+        log::warn!("Failed to list caves: {}", e);
+        vec![]
+    });
+    paths
+        .iter()
+        .filter_map(|path| {
+            Cave::load(path)
+                .map_err(|e| log::warn!("Failed to load cave {}: {}", path.display(), e))
+                .ok()
+        })
+        .collect()
+}
 
-fn load_sprites(world: &mut World) -> Handle<SpriteSheet> {
+fn load_sprites(
+    world: &mut World,
+    theme_name: &str,
+    theme: &Theme,
+    palette: Option<Palette>,
+) -> Handle<SpriteSheet> {
     // Load the texture for our sprites. We'll later need to
     // add a handle to this texture to our `SpriteRender`s, so
     // we need to keep a reference to it.
     let texture_handle = {
         let loader = world.read_resource::<Loader>();
         let texture_storage = world.read_resource::<AssetStorage<Texture>>();
-        loader.load(
-            "sprites/grid.png",
-            ImageFormat::default(),
-            (),
-            &texture_storage,
-        )
+        let path = format!("themes/{}/grid.png", theme_name);
+        let regions = palette
+            .map(|palette| palette_regions(theme_name, theme, palette))
+            .unwrap_or_default();
+        if regions.is_empty() {
+            loader.load(path, ImageFormat::default(), (), &texture_storage)
+        } else {
+            loader.load(path, PalettedImageFormat { regions }, (), &texture_storage)
+        }
     };
 
     // Load the spritesheet definition file, which contains metadata on our
@@ -30,9 +75,43 @@ fn load_sprites(world: &mut World) -> Handle<SpriteSheet> {
     let loader = world.read_resource::<Loader>();
     let sheet_storage = world.read_resource::<AssetStorage<SpriteSheet>>();
     loader.load(
-        "sprites/grid.ron",
+        format!("themes/{}/grid.ron", theme_name),
         SpriteSheetFormat(texture_handle),
         (),
         &sheet_storage,
     )
 }
+
+// which parts of the sprite sheet get painted with which colour
+fn palette_regions(theme_name: &str, theme: &Theme, palette: Palette) -> Vec<(SpriteRect, Rgb)> {
+    theme
+        .palette_regions(theme_name, palette)
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to read sprite positions, not using palette: {}", e);
+            vec![]
+        })
+}
+
+/// Sprite sheet image, with some of the sprites painted in the cave colours
+#[derive(Debug, Clone)]
+struct PalettedImageFormat {
+    regions: Vec<(SpriteRect, Rgb)>,
+}
+
+impl Format<TextureData> for PalettedImageFormat {
+    fn name(&self) -> &'static str {
+        "PALETTED_IMAGE"
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<TextureData, amethyst::Error> {
+        let mut image = image::load_from_memory(&bytes)?.to_rgba();
+        let width = image.width() as usize;
+        for (rect, color) in &self.regions {
+            palette::recolor(&mut image, width, *rect, *color);
+        }
+
+        let mut png = vec![];
+        image::DynamicImage::ImageRgba8(image).write_to(&mut png, image::ImageOutputFormat::PNG)?;
+        ImageFormat::default().import_simple(png)
+    }
+}
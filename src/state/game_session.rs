@@ -0,0 +1,117 @@
+use amethyst::{input::is_close_requested, prelude::*};
+
+use std::collections::VecDeque;
+
+use super::{Interlude, PlayingMap};
+use crate::{
+    map::Cave,
+    session::{CaveEnd, CaveResult, Session},
+};
+
+// what the game does next, each one a state pushed on top of the session
+// (or leaving it)
+enum Step {
+    Intro,
+    Play,
+    Message(Vec<String>),
+    Leave,
+}
+
+/// A game through the caves of `resources/map` in order, on a few lives;
+/// the caves it plays leave a `CaveResult` behind when they end
+pub struct GameSession {
+    caves: Vec<Cave>,
+    session: Session,
+    steps: VecDeque<Step>,
+}
+
+impl Default for GameSession {
+    fn default() -> Self {
+        GameSession {
+            caves: vec![],
+            session: Session::new(0),
+            steps: VecDeque::new(),
+        }
+    }
+}
+
+impl SimpleState for GameSession {
+    fn on_start(&mut self, _data: StateData<'_, GameData<'_, '_>>) {
+        self.caves = super::load_caves();
+        self.session = Session::new(self.caves.len());
+        if self.caves.is_empty() {
+            self.steps
+                .push_back(Step::Message(vec!["No caves to play".to_string()]));
+            self.steps.push_back(Step::Leave);
+        } else {
+            self.steps.push_back(Step::Intro);
+            self.steps.push_back(Step::Play);
+        }
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world.remove::<Session>();
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        if let Some(result) = data.world.remove::<CaveResult>() {
+            self.cave_over(result);
+        }
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        match self.steps.pop_front() {
+            Some(Step::Intro) => Trans::Push(Box::new(Interlude::new(vec![
+                self.session.intro(),
+                format!("Lives: {}", self.session.lives),
+            ]))),
+            Some(Step::Play) => {
+                // for the HUD
+                data.world.insert(self.session.clone());
+                let cave = self.caves[self.session.cave].clone();
+                Trans::Push(Box::new(PlayingMap::in_session(cave, self.session.level)))
+            }
+            Some(Step::Message(lines)) => Trans::Push(Box::new(Interlude::new(lines))),
+            Some(Step::Leave) => Trans::Pop,
+            None => Trans::None,
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        _data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(&event) {
+                return Trans::Quit;
+            }
+        }
+        Trans::None
+    }
+}
+
+impl GameSession {
+    fn cave_over(&mut self, result: CaveResult) {
+        self.session.cave_over(result);
+
+        if result.end == CaveEnd::Quit {
+            self.steps.push_back(Step::Leave);
+            return;
+        }
+        if result.end == CaveEnd::OutOfTime {
+            self.steps
+                .push_back(Step::Message(vec!["Out of time".to_string()]));
+        }
+        if self.session.is_over() {
+            self.steps.push_back(Step::Message(vec![
+                "Game over".to_string(),
+                format!("Score: {:0>6}", self.session.score),
+            ]));
+            self.steps.push_back(Step::Leave);
+        } else {
+            self.steps.push_back(Step::Intro);
+            self.steps.push_back(Step::Play);
+        }
+    }
+}
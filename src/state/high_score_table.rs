@@ -0,0 +1,151 @@
+use amethyst::{
+    assets::Loader,
+    ecs::prelude::*,
+    input::{get_key, is_close_requested, ElementState, InputEvent, VirtualKeyCode},
+    prelude::*,
+    ui::{Anchor, TtfFormat, UiText, UiTransform},
+};
+
+use crate::{
+    game,
+    highscores::{self, HighScores},
+    map::Cave,
+    session,
+};
+
+/// The high scores of one cave at a time, on every level played;
+/// `left` and `right` go through the caves
+#[derive(Default)]
+pub struct HighScoreTable {
+    caves: Vec<Cave>,
+    scores: HighScores,
+    cave: usize,
+    entities: Vec<Entity>,
+}
+
+impl SimpleState for HighScoreTable {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.caves = super::load_caves();
+        self.scores = highscores::user_path()
+            .map(|path| {
+                HighScores::load(path).unwrap_or_else(|e| {
+                    log::warn!("Failed to load high scores: {}", e);
+                    HighScores::default()
+                })
+            })
+            .unwrap_or_default();
+        self.show(data.world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.hide(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        match &event {
+            StateEvent::Window(event) => {
+                if is_close_requested(&event) {
+                    return Trans::Quit;
+                }
+                match get_key(&event) {
+                    Some((VirtualKeyCode::Escape, ElementState::Pressed))
+                    | Some((VirtualKeyCode::Return, ElementState::Pressed)) => return Trans::Pop,
+                    _ => {}
+                }
+            }
+            StateEvent::Input(InputEvent::ActionPressed(action)) => {
+                let count = self.caves.len().max(1);
+                match action.as_str() {
+                    "fire" => return Trans::Pop,
+                    "left" => self.cave = (self.cave + count - 1) % count,
+                    "right" => self.cave = (self.cave + 1) % count,
+                    _ => return Trans::None,
+                }
+                self.hide(data.world);
+                self.show(data.world);
+            }
+            _ => {}
+        }
+        Trans::None
+    }
+}
+
+impl HighScoreTable {
+    fn lines(&self) -> Vec<(String, f32)> {
+        let cave = match self.caves.get(self.cave) {
+            Some(cave) => cave,
+            None => return vec![("No caves".to_string(), 40.)],
+        };
+        let mut lines = vec![(
+            format!("Cave {}: {}", session::cave_letter(self.cave), cave.name()),
+            40.,
+        )];
+
+        let tables = self
+            .scores
+            .tables
+            .iter()
+            .filter(|(key, _)| key.pack == cave.pack && key.cave == cave.id);
+        for (key, entries) in tables {
+            lines.push((format!("Level {}", key.level), 30.));
+            for (i, entry) in entries.iter().enumerate() {
+                lines.push((
+                    format!(
+                        "{:>2}. {:<12} {:0>6} {:>4}s {:>3} diamonds",
+                        i + 1,
+                        entry.name,
+                        entry.score,
+                        game::cave_time(entry.ticks).as_secs(),
+                        entry.diamonds
+                    ),
+                    25.,
+                ));
+            }
+        }
+        if lines.len() == 1 {
+            lines.push(("No high scores yet".to_string(), 30.));
+        }
+        lines
+    }
+
+    fn show(&mut self, world: &mut World) {
+        let font = world.read_resource::<Loader>().load(
+            "font/bd.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+        let lines = std::iter::once(("High Scores".to_string(), 60.)).chain(self.lines());
+
+        let mut y = -40.;
+        for (i, (text, size)) in lines.enumerate() {
+            let entity = world
+                .create_entity()
+                .with(UiTransform::new(
+                    format!("high_scores_{}", i),
+                    Anchor::TopMiddle,
+                    Anchor::TopMiddle,
+                    0.,
+                    y,
+                    1.,
+                    1000.,
+                    size + 10.,
+                ))
+                .with(UiText::new(font.clone(), text, [1., 1., 1., 1.], size))
+                .build();
+            self.entities.push(entity);
+            y -= size + 10.;
+        }
+    }
+
+    fn hide(&mut self, world: &mut World) {
+        world
+            .delete_entities(&self.entities)
+            .expect("high score entities are alive");
+        self.entities.clear();
+    }
+}
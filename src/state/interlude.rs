@@ -0,0 +1,103 @@
+use amethyst::{
+    assets::Loader,
+    core::Time,
+    ecs::prelude::*,
+    input::{get_key, is_close_requested, ElementState, InputEvent, VirtualKeyCode},
+    prelude::*,
+    ui::{Anchor, TtfFormat, UiText, UiTransform},
+};
+
+// seconds an interlude stays up if nothing is pressed
+const SHOWN_FOR: f32 = 3.;
+
+/// A few lines of text between caves, like "Cave B, Level 1" or "Game over";
+/// goes away after a while, or with `fire`, Enter or Escape
+pub struct Interlude {
+    lines: Vec<String>,
+    elapsed: f32,
+    entities: Vec<Entity>,
+}
+
+impl Interlude {
+    pub fn new(lines: Vec<String>) -> Self {
+        Interlude {
+            lines,
+            elapsed: 0.,
+            entities: vec![],
+        }
+    }
+}
+
+impl SimpleState for Interlude {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        let font = world.read_resource::<Loader>().load(
+            "font/bd.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+
+        let mut y = 30. * (self.lines.len() as f32 - 1.);
+        for (i, line) in self.lines.iter().enumerate() {
+            let entity = world
+                .create_entity()
+                .with(UiTransform::new(
+                    format!("interlude_{}", i),
+                    Anchor::Middle,
+                    Anchor::Middle,
+                    0.,
+                    y,
+                    1.,
+                    800.,
+                    60.,
+                ))
+                .with(UiText::new(
+                    font.clone(),
+                    line.clone(),
+                    [1., 1., 1., 1.],
+                    50.,
+                ))
+                .build();
+            self.entities.push(entity);
+            y -= 60.;
+        }
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world
+            .delete_entities(&self.entities)
+            .expect("interlude entities are alive");
+        self.entities.clear();
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        self.elapsed += data.world.read_resource::<Time>().delta_seconds();
+        if self.elapsed >= SHOWN_FOR {
+            Trans::Pop
+        } else {
+            Trans::None
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        _data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        match &event {
+            StateEvent::Window(event) => {
+                if is_close_requested(&event) {
+                    return Trans::Quit;
+                }
+                match get_key(&event) {
+                    Some((VirtualKeyCode::Return, ElementState::Pressed))
+                    | Some((VirtualKeyCode::Escape, ElementState::Pressed)) => Trans::Pop,
+                    _ => Trans::None,
+                }
+            }
+            StateEvent::Input(InputEvent::ActionPressed(action)) if action == "fire" => Trans::Pop,
+            _ => Trans::None,
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use amethyst::{
+    assets::{AssetStorage, Format, Handle, Loader},
+    ecs::prelude::*,
+    input::{is_close_requested, is_key_down, VirtualKeyCode},
+    prelude::*,
+    renderer::{ImageFormat, Texture},
+    ui::{Anchor, TtfFormat, UiImage, UiText, UiTransform},
+};
+
+use super::{menu::Menu, PlayingMap};
+use crate::{
+    game,
+    grid::GridState,
+    highscores::TableKey,
+    map::Cave,
+    records::{self, CaveRecord, Records},
+    render::Renderer,
+    theme,
+};
+
+// biggest size of the minimap of the selected cave
+const PREVIEW_WIDTH: u32 = 480;
+const PREVIEW_HEIGHT: u32 = 240;
+
+/// The caves of `resources/map`, with a look at the selected one and its
+/// best results; a cave opens once the one before it is completed
+#[derive(Default)]
+pub struct LevelSelect {
+    caves: Vec<Cave>,
+    records: Records,
+    menu: Option<Menu>,
+    // minimap of every cave, if it could be drawn
+    previews: Vec<Option<Handle<Texture>>>,
+    // which cave the details are showing
+    shown: Option<usize>,
+    details: Vec<Entity>,
+}
+
+impl SimpleState for LevelSelect {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        self.caves = super::load_caves();
+
+        let theme_name = world
+            .try_fetch::<theme::SelectedTheme>()
+            .map(|selected| selected.0.clone())
+            .unwrap_or_else(|| theme::DEFAULT_THEME.to_string());
+        self.previews = self
+            .caves
+            .iter()
+            .map(|cave| {
+                preview_texture(world, cave, &theme_name)
+                    .map_err(|e| log::warn!("Failed to draw cave {}: {}", cave.id, e))
+                    .ok()
+            })
+            .collect();
+
+        self.show(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.hide(data.world);
+    }
+
+    fn on_pause(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.hide(data.world);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        // the cave just played may have opened the next one
+        self.show(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(&event) {
+                return Trans::Quit;
+            }
+            if is_key_down(&event, VirtualKeyCode::Escape) {
+                return Trans::Pop;
+            }
+        }
+
+        let menu = match self.menu.as_mut() {
+            Some(menu) => menu,
+            None => return Trans::None,
+        };
+        let picked = menu.handle_event(data.world, &event);
+        let selected = menu.selected();
+        if self.shown != Some(selected) {
+            self.show_details(data.world, selected);
+        }
+
+        match picked.and_then(|i| self.caves.get(i)) {
+            Some(cave) => Trans::Push(Box::new(PlayingMap::new(cave.clone()))),
+            None => Trans::None,
+        }
+    }
+}
+
+impl LevelSelect {
+    fn show(&mut self, world: &mut World) {
+        self.records = records::user_path()
+            .map(|path| {
+                Records::load(path).unwrap_or_else(|e| {
+                    log::warn!("Failed to load records: {}", e);
+                    Records::default()
+                })
+            })
+            .unwrap_or_default();
+
+        let keys: Vec<TableKey> = self.caves.iter().map(table_key).collect();
+        let items: Vec<(&str, bool)> = self
+            .caves
+            .iter()
+            .enumerate()
+            .map(|(i, cave)| (cave.name(), self.records.unlocked(&keys, i)))
+            .collect();
+        let mut menu = Menu::new("Level Select", &items);
+        menu.show(world);
+        let selected = menu.selected();
+        self.menu = Some(menu);
+        self.show_details(world, selected);
+    }
+
+    fn hide(&mut self, world: &mut World) {
+        if let Some(mut menu) = self.menu.take() {
+            menu.hide(world);
+        }
+        self.hide_details(world);
+    }
+
+    fn hide_details(&mut self, world: &mut World) {
+        world
+            .delete_entities(&self.details)
+            .expect("detail entities are alive");
+        self.details.clear();
+        self.shown = None;
+    }
+
+    fn show_details(&mut self, world: &mut World, i: usize) {
+        self.hide_details(world);
+        let cave = match self.caves.get(i) {
+            Some(cave) => cave,
+            None => return,
+        };
+        let keys: Vec<TableKey> = self.caves.iter().map(table_key).collect();
+        let record = self.records.get(&keys[i]);
+        let status = if !self.records.unlocked(&keys, i) {
+            "Locked"
+        } else if record.completed {
+            "Completed"
+        } else {
+            "Not completed yet"
+        };
+        let lines = [
+            format!("Diamonds needed: {}", cave.map.params.diamonds_needed),
+            describe_best(&record),
+            status.to_string(),
+        ];
+
+        let font = world.read_resource::<Loader>().load(
+            "font/bd.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+        let mut y = 20. + 40. * lines.len() as f32;
+        for (n, line) in lines.iter().enumerate() {
+            let entity = world
+                .create_entity()
+                .with(UiTransform::new(
+                    format!("level_detail_{}", n),
+                    Anchor::BottomMiddle,
+                    Anchor::BottomMiddle,
+                    0.,
+                    y,
+                    1.,
+                    800.,
+                    40.,
+                ))
+                .with(UiText::new(
+                    font.clone(),
+                    line.clone(),
+                    [1., 1., 1., 1.],
+                    30.,
+                ))
+                .build();
+            self.details.push(entity);
+            y -= 40.;
+        }
+
+        if let Some(texture) = self.previews.get(i).and_then(|preview| preview.clone()) {
+            let (width, height) = preview_size(cave.map.width, cave.map.height);
+            let entity = world
+                .create_entity()
+                .with(UiTransform::new(
+                    "level_preview".to_string(),
+                    Anchor::BottomMiddle,
+                    Anchor::BottomMiddle,
+                    0.,
+                    60. + 40. * lines.len() as f32,
+                    1.,
+                    width,
+                    height,
+                ))
+                .with(UiImage::Texture(texture))
+                .build();
+            self.details.push(entity);
+        }
+
+        self.shown = Some(i);
+    }
+}
+
+// the records of a cave played from here, which is on the first level
+fn table_key(cave: &Cave) -> TableKey {
+    TableKey {
+        pack: cave.pack.clone(),
+        cave: cave.id.clone(),
+        level: 1,
+    }
+}
+
+fn describe_best(record: &CaveRecord) -> String {
+    let score = record
+        .best_score
+        .map_or("-".to_string(), |score| score.to_string());
+    let time = record.best_ticks.map_or("-".to_string(), |ticks| {
+        format!("{}s", game::cave_time(ticks).as_secs())
+    });
+    format!("Best score: {}   Best time: {}", score, time)
+}
+
+// the size the minimap of a `width` by `height` cave is drawn at
+fn preview_size(width: usize, height: usize) -> (f32, f32) {
+    let scale = (PREVIEW_WIDTH as f32 / width as f32).min(PREVIEW_HEIGHT as f32 / height as f32);
+    (width as f32 * scale, height as f32 * scale)
+}
+
+// the cave at its start, drawn on the CPU into a texture of its own
+fn preview_texture(
+    world: &World,
+    cave: &Cave,
+    theme_name: &str,
+) -> Result<Handle<Texture>, amethyst::Error> {
+    let renderer = Renderer::load(theme_name, cave.map.params.palette)
+        .map_err(|e| amethyst::Error::from_string(e.to_string()))?;
+    let grid = GridState::from_map(cave.map.clone(), 0);
+    let image = renderer.preview(&grid, PREVIEW_WIDTH, PREVIEW_HEIGHT);
+
+    let mut png = vec![];
+    image::DynamicImage::ImageRgba8(image).write_to(&mut png, image::ImageOutputFormat::PNG)?;
+    let data = ImageFormat::default().import_simple(png)?;
+
+    Ok(world.read_resource::<Loader>().load_from_data(
+        data,
+        (),
+        &world.read_resource::<AssetStorage<Texture>>(),
+    ))
+}
@@ -1,53 +1,95 @@
 use amethyst::{
-    input::{get_key, is_close_requested, is_key_down, ElementState, VirtualKeyCode},
+    input::{is_close_requested, is_key_down, VirtualKeyCode},
     prelude::*,
 };
+use anyhow::{format_err, Result};
 
-use super::PlayingMap;
+use super::{menu::Menu, GameSession, HighScoreTable, LevelSelect, PlayingMap, Settings};
+use crate::{game, map::Cave, replay::Replay};
+
+const PLAY: usize = 0;
+const LEVEL_SELECT: usize = 1;
+const REPLAYS: usize = 2;
+const HIGH_SCORES: usize = 3;
+const SETTINGS: usize = 4;
+const QUIT: usize = 6;
 
-#[derive(Default)]
 pub struct MainScreen {
-    started: bool,
+    menu: Menu,
 }
 
-impl SimpleState for MainScreen {
-    // until we have a functional full-screen menu, just jump into the game
-    fn update(&mut self, _data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
-        if !self.started {
-            self.started = true;
-            Trans::Push(Box::new(PlayingMap::default()))
-        } else {
-            Trans::Quit
+impl Default for MainScreen {
+    fn default() -> Self {
+        // the greyed out one is still to come
+        MainScreen {
+            menu: Menu::new(
+                "Boulder Dash",
+                &[
+                    ("Play", true),
+                    ("Level Select", true),
+                    ("Replays", true),
+                    ("High Scores", true),
+                    ("Settings", true),
+                    ("Editor", false),
+                    ("Quit", true),
+                ],
+            ),
         }
     }
+}
+
+impl SimpleState for MainScreen {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.menu.show(data.world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.menu.hide(data.world);
+    }
+
+    fn on_pause(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.menu.hide(data.world);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.menu.show(data.world);
+    }
 
     fn handle_event(
         &mut self,
-        _data: StateData<'_, GameData<'_, '_>>,
+        data: StateData<'_, GameData<'_, '_>>,
         event: StateEvent,
     ) -> SimpleTrans {
         if let StateEvent::Window(event) = &event {
-            // Check if the window should be closed
             if is_close_requested(&event) || is_key_down(&event, VirtualKeyCode::Escape) {
                 return Trans::Quit;
             }
+        }
 
-            // Listen to any key events
-            if let Some(event) = get_key(&event) {
-                match event {
-                    (_, ElementState::Pressed) => {
-                        return Trans::Push(Box::new(PlayingMap::default()))
-                    }
-                    _ => {}
+        match self.menu.handle_event(data.world, &event) {
+            Some(PLAY) => Trans::Push(Box::new(GameSession::default())),
+            Some(LEVEL_SELECT) => Trans::Push(Box::new(LevelSelect::default())),
+            Some(REPLAYS) => match last_replay() {
+                Ok((cave, replay)) => Trans::Push(Box::new(PlayingMap::replaying(cave, replay))),
+                Err(e) => {
+                    log::warn!("Failed to play back the last game: {}", e);
+                    Trans::None
                 }
-            }
-
-            // If you're looking for a more sophisticated event handling solution,
-            // including key bindings and gamepad support, please have a look at
-            // https://book.amethyst.rs/stable/pong-tutorial/pong-tutorial-03.html#capturing-user-input
+            },
+            Some(HIGH_SCORES) => Trans::Push(Box::new(HighScoreTable::default())),
+            Some(SETTINGS) => Trans::Push(Box::new(Settings::default())),
+            Some(QUIT) => Trans::Quit,
+            _ => Trans::None,
         }
-
-        // Keep going
-        Trans::None
     }
 }
+
+// the replay of the last game played, and its cave
+fn last_replay() -> Result<(Cave, Replay)> {
+    let replay = Replay::load(game::last_replay_path())?;
+    let path = replay
+        .cave
+        .clone()
+        .ok_or_else(|| format_err!("The replay doesn't say which cave it was played in"))?;
+    Ok((Cave::load(path)?, replay))
+}
@@ -0,0 +1,164 @@
+use amethyst::{
+    assets::Loader,
+    ecs::prelude::*,
+    input::{get_key, ElementState, InputEvent, VirtualKeyCode},
+    prelude::*,
+    ui::{Anchor, TtfFormat, UiText, UiTransform},
+};
+
+const NORMAL: [f32; 4] = [0.6, 0.6, 0.6, 1.];
+const SELECTED: [f32; 4] = [1., 1., 1., 1.];
+const DISABLED: [f32; 4] = [0.25, 0.25, 0.25, 1.];
+
+/// A title with a list of items under it, picked with the `up`, `down` and
+/// `fire` actions (or Enter), so both keyboard and gamepad work
+pub struct Menu {
+    title: String,
+    // label, and whether it can be picked
+    items: Vec<(String, bool)>,
+    selected: usize,
+    entities: Vec<Entity>,
+}
+
+impl Menu {
+    pub fn new(title: &str, items: &[(&str, bool)]) -> Self {
+        let items: Vec<(String, bool)> = items
+            .iter()
+            .map(|(label, enabled)| (label.to_string(), *enabled))
+            .collect();
+        let selected = items.iter().position(|(_, enabled)| *enabled).unwrap_or(0);
+
+        Menu {
+            title: title.to_string(),
+            items,
+            selected,
+            entities: vec![],
+        }
+    }
+
+    /// The menu with the cursor on item `index` to begin with
+    pub fn with_selected(mut self, index: usize) -> Self {
+        if self.items.get(index).map_or(false, |(_, enabled)| *enabled) {
+            self.selected = index;
+        }
+        self
+    }
+
+    pub fn show(&mut self, world: &mut World) {
+        if !self.entities.is_empty() {
+            return;
+        }
+
+        let font = world.read_resource::<Loader>().load(
+            "font/bd.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+        let lines = std::iter::once((self.title.as_str(), 60.))
+            .chain(self.items.iter().map(|(label, _)| (label.as_str(), 40.)));
+
+        let mut y = -80.;
+        for (i, (text, size)) in lines.enumerate() {
+            let entity = world
+                .create_entity()
+                .with(UiTransform::new(
+                    format!("menu_{}", i),
+                    Anchor::TopMiddle,
+                    Anchor::TopMiddle,
+                    0.,
+                    y,
+                    1.,
+                    800.,
+                    size + 10.,
+                ))
+                .with(UiText::new(font.clone(), text.to_string(), NORMAL, size))
+                .build();
+            self.entities.push(entity);
+            y -= size + 20.;
+        }
+
+        self.refresh(world);
+    }
+
+    pub fn hide(&mut self, world: &mut World) {
+        world
+            .delete_entities(&self.entities)
+            .expect("menu entities are alive");
+        self.entities.clear();
+    }
+
+    /// Change the text of item `index`
+    pub fn set_label(&mut self, world: &World, index: usize, label: String) {
+        let entity = self.entities.get(index + 1).copied();
+        if let Some(text) = entity.and_then(|e| world.write_storage::<UiText>().get_mut(e)) {
+            text.text = label.clone();
+        }
+        if let Some(item) = self.items.get_mut(index) {
+            item.0 = label;
+        }
+    }
+
+    /// Index of the item under the cursor
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Index of the item picked by this event, if any
+    pub fn handle_event(&mut self, world: &mut World, event: &StateEvent) -> Option<usize> {
+        match event {
+            StateEvent::Input(InputEvent::ActionPressed(action)) => match action.as_str() {
+                "up" => self.select(self.items.len() - 1, world),
+                "down" => self.select(1, world),
+                "fire" => return self.picked(),
+                _ => {}
+            },
+            StateEvent::Window(event) => {
+                if get_key(event) == Some((VirtualKeyCode::Return, ElementState::Pressed)) {
+                    return self.picked();
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    // move the selection `step` items down (wrapping around), skipping the
+    // ones that can't be picked
+    fn select(&mut self, step: usize, world: &mut World) {
+        for _ in 0..self.items.len() {
+            self.selected = (self.selected + step) % self.items.len();
+            if self.items[self.selected].1 {
+                break;
+            }
+        }
+        self.refresh(world);
+    }
+
+    fn picked(&self) -> Option<usize> {
+        Some(self.selected).filter(|i| self.items.get(*i).map_or(false, |(_, enabled)| *enabled))
+    }
+
+    fn refresh(&self, world: &World) {
+        let mut texts = world.write_storage::<UiText>();
+        if let Some(title) = self.entities.first().and_then(|e| texts.get_mut(*e)) {
+            title.color = SELECTED;
+        }
+        for (i, ((_, enabled), entity)) in self
+            .items
+            .iter()
+            .zip(self.entities.iter().skip(1))
+            .enumerate()
+        {
+            if let Some(text) = texts.get_mut(*entity) {
+                text.color = if !enabled {
+                    DISABLED
+                } else if i == self.selected {
+                    SELECTED
+                } else {
+                    NORMAL
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use amethyst::{
+    assets::Loader,
+    ecs::prelude::*,
+    input::{get_key, is_close_requested, ElementState, InputEvent, VirtualKeyCode},
+    prelude::*,
+    ui::{Anchor, TtfFormat, UiText, UiTransform},
+    winit::{Event, WindowEvent},
+};
+
+use crate::highscores::{self, Entry, HighScores, TableKey};
+
+const MAX_NAME_LEN: usize = 12;
+
+/// Asks for the name to put a new high score under, then saves it; Enter
+/// (or `fire`) saves, Escape leaves the score out
+pub struct NameEntry {
+    key: TableKey,
+    entry: Entry,
+    cave_name: String,
+    name_text: Option<Entity>,
+    entities: Vec<Entity>,
+}
+
+impl NameEntry {
+    pub fn new(key: TableKey, entry: Entry, cave_name: &str) -> Self {
+        NameEntry {
+            key,
+            entry,
+            cave_name: cave_name.to_string(),
+            name_text: None,
+            entities: vec![],
+        }
+    }
+
+    fn refresh(&self, world: &World) {
+        let mut texts = world.write_storage::<UiText>();
+        if let Some(text) = self.name_text.and_then(|entity| texts.get_mut(entity)) {
+            text.text = format!("Name: {}_", self.entry.name);
+        }
+    }
+
+    fn save(&self) {
+        let path = match highscores::user_path() {
+            Some(path) => path,
+            None => return,
+        };
+        let mut entry = self.entry.clone();
+        if entry.name.trim().is_empty() {
+            entry.name = "Player".to_string();
+        }
+        let result = HighScores::load(&path).and_then(|mut scores| {
+            scores.insert(self.key.clone(), entry);
+            scores.save(&path)
+        });
+        if let Err(e) = result {
+            log::warn!("Failed to save high scores: {}", e);
+        }
+    }
+}
+
+impl SimpleState for NameEntry {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        // the name given the time before, if any
+        self.entry.name = highscores::user_path()
+            .and_then(|path| HighScores::load(path).ok())
+            .and_then(|scores| scores.last_name)
+            .unwrap_or_default();
+
+        let font = world.read_resource::<Loader>().load(
+            "font/bd.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+        let lines = [
+            "New high score!".to_string(),
+            format!(
+                "{}, Level {}: {:0>6}",
+                self.cave_name, self.key.level, self.entry.score
+            ),
+            String::new(),
+        ];
+        let mut y = 60.;
+        for (i, line) in lines.iter().enumerate() {
+            let entity = world
+                .create_entity()
+                .with(UiTransform::new(
+                    format!("name_entry_{}", i),
+                    Anchor::Middle,
+                    Anchor::Middle,
+                    0.,
+                    y,
+                    1.,
+                    800.,
+                    60.,
+                ))
+                .with(UiText::new(
+                    font.clone(),
+                    line.clone(),
+                    [1., 1., 1., 1.],
+                    50.,
+                ))
+                .build();
+            self.entities.push(entity);
+            y -= 60.;
+        }
+        self.name_text = self.entities.last().cloned();
+        self.refresh(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world
+            .delete_entities(&self.entities)
+            .expect("name entry entities are alive");
+        self.entities.clear();
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        match &event {
+            StateEvent::Window(event) => {
+                if is_close_requested(&event) {
+                    return Trans::Quit;
+                }
+                match get_key(&event) {
+                    Some((VirtualKeyCode::Return, ElementState::Pressed)) => {
+                        self.save();
+                        return Trans::Pop;
+                    }
+                    Some((VirtualKeyCode::Escape, ElementState::Pressed)) => return Trans::Pop,
+                    Some((VirtualKeyCode::Back, ElementState::Pressed)) => {
+                        self.entry.name.pop();
+                    }
+                    _ => {}
+                }
+                if let Event::WindowEvent {
+                    event: WindowEvent::ReceivedCharacter(c),
+                    ..
+                } = event
+                {
+                    if !c.is_control() && self.entry.name.chars().count() < MAX_NAME_LEN {
+                        self.entry.name.push(*c);
+                    }
+                }
+                self.refresh(data.world);
+            }
+            StateEvent::Input(InputEvent::ActionPressed(action)) if action == "fire" => {
+                self.save();
+                return Trans::Pop;
+            }
+            _ => {}
+        }
+        Trans::None
+    }
+}
@@ -0,0 +1,84 @@
+use amethyst::{
+    input::{is_close_requested, is_key_down, VirtualKeyCode},
+    prelude::*,
+};
+
+use super::menu::Menu;
+
+/// What the player picked in the pause menu, for the paused cave to act on
+/// when it resumes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PauseChoice {
+    Restart,
+    GiveUp,
+    QuitToMenu,
+}
+
+/// Menu on top of a cave, which stands still meanwhile
+pub struct Pause {
+    menu: Menu,
+}
+
+impl Default for Pause {
+    fn default() -> Self {
+        Pause {
+            menu: Menu::new(
+                "Paused",
+                &[
+                    ("Resume", true),
+                    ("Restart cave", true),
+                    ("Give up", true),
+                    ("Quit to menu", true),
+                ],
+            ),
+        }
+    }
+}
+
+impl Pause {
+    /// The menu with "Restart cave" under the cursor, so that restarting
+    /// takes a second key press
+    pub fn restarting() -> Self {
+        let Pause { menu } = Pause::default();
+        Pause {
+            menu: menu.with_selected(1),
+        }
+    }
+}
+
+impl SimpleState for Pause {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.menu.show(data.world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.menu.hide(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(&event) {
+                return Trans::Quit;
+            }
+            if is_key_down(&event, VirtualKeyCode::Escape) {
+                return Trans::Pop;
+            }
+        }
+
+        let choice = match self.menu.handle_event(data.world, &event) {
+            Some(0) => None,
+            Some(1) => Some(PauseChoice::Restart),
+            Some(2) => Some(PauseChoice::GiveUp),
+            Some(3) => Some(PauseChoice::QuitToMenu),
+            _ => return Trans::None,
+        };
+        if let Some(choice) = choice {
+            data.world.insert(choice);
+        }
+        Trans::Pop
+    }
+}
@@ -1,21 +1,83 @@
 use amethyst::{
     assets::Handle,
-    core::{math::Vector3, Transform},
+    core::{math::Vector3, Time, Transform},
     ecs::{prelude::*, NullStorage},
-    input::{get_key, is_close_requested, is_key_down, ElementState, VirtualKeyCode},
+    input::{
+        get_key, is_close_requested, is_key_down, ControllerAxis, ElementState, InputEvent,
+        InputHandler, StringBindings, VirtualKeyCode,
+    },
     prelude::*,
     renderer::{SpriteRender, SpriteSheet},
     window::ScreenDimensions,
 };
 
-use crate::{camera, grid, input, score};
+use rand::{thread_rng, Rng};
+use std::{collections::HashMap, ops::Range};
+
+use crate::{
+    animation, camera,
+    game::{self, FIXED_UPDATES_PER_SECOND, FIXED_UPDATES_PER_TICK},
+    grid,
+    highscores::{self, HighScores, TableKey},
+    input,
+    map::Cave,
+    motion, records, replay, score,
+    session::{CaveEnd, CaveResult},
+    theme,
+};
 
-#[derive(Default)]
 pub struct PlayingMap<'a, 'b> {
+    cave: Cave,
+    // difficulty level, which the high scores are kept by
+    level: u32,
+    // part of a game on lives, which the cave hands its result to when it
+    // ends, instead of waiting for a restart
+    in_session: bool,
+    // whether the result of this game went into the records already
+    recorded: bool,
+    // fixed updates since the cave ended
+    over_for: u64,
+    // asks for the name of a high score just made
+    name_entry: Option<super::NameEntry>,
+    // where to go once the name is in
+    leaving: Option<Leave>,
     dispatcher: Option<Dispatcher<'a, 'b>>,
+    camera: Option<Entity>,
     sprites: Option<Handle<SpriteSheet>>,
+    // how much the sprites are scaled to fit in a grid cell
+    sprite_scale: f32,
     tick_count: u64,
     input_tracker: input::InputTracker,
+    replay: replay::Replay,
+    // a replay being played back, instead of taking the player's input
+    playback: Option<replay::Replay>,
+    // entities of the grid cells near the camera (by cell index),
+    // and the ones not in use at the moment
+    visible_tiles: HashMap<usize, VisibleTile>,
+    spare_entities: Vec<Entity>,
+    // columns and rows of the cells in `visible_tiles`
+    visible_cells: (Range<usize>, Range<usize>),
+    // what to do after the pause menu closed
+    pause_choice: Option<super::PauseChoice>,
+}
+
+enum Leave {
+    Restart,
+    Quit,
+}
+
+// how long an ended cave stays up before going back to the session
+const END_DELAY: u64 = 2 * FIXED_UPDATES_PER_SECOND;
+
+// cells this far outside the camera view still get their sprites
+const VIEW_MARGIN: usize = 2;
+
+// sprite number and the animation (if any) of a grid cell
+type TileLook = (Option<usize>, Option<&'static str>);
+
+struct VisibleTile {
+    entity: Entity,
+    look: TileLook,
 }
 
 impl<'a, 'b> SimpleState for PlayingMap<'a, 'b> {
@@ -24,27 +86,49 @@ impl<'a, 'b> SimpleState for PlayingMap<'a, 'b> {
     // https://book.amethyst.rs/stable/concepts/state.html#life-cycle
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         let world = data.world;
-        world.insert(grid::GridState::new());
+        let seed = match &self.playback {
+            Some(playback) => playback.seed,
+            None => thread_rng().gen(),
+        };
+        let grid = grid::GridState::from_map(self.cave.map.clone(), seed);
+        self.replay = replay::Replay::new(grid.seed());
+        self.replay.cave = Some(self.cave.path.clone());
+        let input_config = input::InputConfig::load(input::INPUT_CONFIG).unwrap_or_else(|e| {
+            log::warn!("Failed to load input config: {}", e);
+            input::InputConfig::default()
+        });
+        self.input_tracker = input::InputTracker::new(input_config.buffer_mode);
+        world.insert(grid);
 
         // Get the screen dimensions so we can initialize the camera and
         // place our sprites correctly later. We'll clone this since we'll
         // pass the world mutably to the following functions.
         let dimensions = (*world.read_resource::<ScreenDimensions>()).clone();
         world.register::<GridSprite>();
+        world.register::<motion::TileMotion>();
+        world.register::<animation::Animated>();
 
         // Load our sprites and display them
-        let sprites = super::load_sprites(world);
-        self.sprites = Some(sprites);
+        if !world.has_value::<theme::SelectedTheme>() {
+            world.insert(theme::SelectedTheme::default());
+        }
+        self.load_theme(world);
 
         score::initialise_scoreboard(world);
 
-        camera::CameraSystem::init(world, &dimensions);
+        self.camera = Some(camera::CameraSystem::init(world, &dimensions));
 
         // Create the `DispatcherBuilder` and register some `System`s that should only run for this `State`.
         let mut dispatcher_builder = DispatcherBuilder::new();
 
         dispatcher_builder.add(camera::CameraSystem::default(), "camera_system", &[]);
         dispatcher_builder.add(score::ScoreSystem::default(), "score_system", &[]);
+        dispatcher_builder.add(motion::MotionSystem::default(), "motion_system", &[]);
+        dispatcher_builder.add(
+            animation::AnimationSystem::default(),
+            "animation_system",
+            &[],
+        );
         // Build and setup the `Dispatcher`.
         let mut dispatcher = dispatcher_builder.build();
         dispatcher.setup(world);
@@ -52,27 +136,100 @@ impl<'a, 'b> SimpleState for PlayingMap<'a, 'b> {
         self.dispatcher = Some(dispatcher);
     }
 
-    fn on_stop(&mut self, _data: StateData<'_, GameData<'_, '_>>) {
-        score::clear_scoreboard(_data.world);
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        score::clear_scoreboard(data.world);
+        self.clear_grid(data.world);
+        if let Some(camera) = self.camera.take() {
+            data.world
+                .delete_entity(camera)
+                .expect("camera entity is alive");
+        }
+
+        if self.playback.is_none() {
+            if let Err(e) = game::save_last_replay(&self.replay) {
+                log::warn!("Failed to save replay: {}", e);
+            }
+        }
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        // keys may have been let go while the cave was paused
+        self.input_tracker.release_all();
+        self.pause_choice = data.world.remove::<super::PauseChoice>();
     }
 
     fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if let Some(leave) = self.leaving.take() {
+            return self.leave(data.world, leave);
+        }
+        match self.pause_choice.take() {
+            Some(super::PauseChoice::Restart) => return self.restart(data.world),
+            Some(super::PauseChoice::QuitToMenu) => return self.leave(data.world, Leave::Quit),
+            Some(super::PauseChoice::GiveUp) => {
+                data.world.write_resource::<grid::GridState>().give_up();
+            }
+            None => {}
+        }
+
+        let alpha = data.world.read_resource::<Time>().interpolation_alpha();
+        let phase = (self.tick_count % FIXED_UPDATES_PER_TICK) as f32 + alpha;
+        data.world
+            .insert(motion::TickProgress(phase / FIXED_UPDATES_PER_TICK as f32));
+
         if let Some(dispatcher) = self.dispatcher.as_mut() {
             dispatcher.dispatch(&data.world);
         }
 
+        // the camera moves between ticks too, bringing new cells into view
+        let cells = {
+            let grid = data.world.read_resource::<grid::GridState>();
+            let view = data.world.read_resource::<camera::CameraView>();
+            view.cells(VIEW_MARGIN, grid.width(), grid.height())
+        };
+        if cells != self.visible_cells {
+            self.redraw_grid(data.world, false);
+        }
+
         camera::CameraSystem::update_screen_dimensions(&mut data.world);
         SimpleTrans::None
     }
 
     fn fixed_update(&mut self, data: StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if let Some(name_entry) = self.name_entry.take() {
+            return Trans::Push(Box::new(name_entry));
+        }
+
+        // a game on lives moves on a while after the cave ended
+        if self.in_session {
+            let grid = data.world.read_resource::<grid::GridState>();
+            if let Some(end) = game::cave_end(&grid) {
+                self.over_for += 1;
+                if self.over_for >= END_DELAY {
+                    let score = game::score(&grid);
+                    drop(grid);
+                    data.world.insert(CaveResult { end, score });
+                    return Trans::Pop;
+                }
+            }
+        }
+
         self.tick_count += 1;
-        if self.tick_count % 8 == 0 {
+        if self.tick_count % FIXED_UPDATES_PER_TICK == 0 {
             {
                 let mut grid = data.world.write_resource::<crate::grid::GridState>();
-                grid.run_tick(self.input_tracker.pop_action());
+                match &self.playback {
+                    Some(playback) => {
+                        let tick = grid.tick_count as usize;
+                        let actions = playback.ticks.get(tick).cloned().unwrap_or_default();
+                        game::replay_tick(&mut grid, actions);
+                    }
+                    None => game::run_tick(&mut grid, &mut self.input_tracker, &mut self.replay),
+                }
+                if game::cave_end(&grid).is_some() && !self.recorded {
+                    self.name_entry = self.record_result(&grid);
+                }
             }
-            self.redraw_grid(data);
+            self.redraw_grid(data.world, true);
         }
 
         SimpleTrans::None
@@ -85,36 +242,69 @@ impl<'a, 'b> SimpleState for PlayingMap<'a, 'b> {
     ) -> SimpleTrans {
         if let StateEvent::Window(event) = &event {
             // Check if the window should be closed
-            if is_close_requested(&event) || is_key_down(&event, VirtualKeyCode::Escape) {
-                return Trans::Pop;
+            if is_close_requested(&event) {
+                return Trans::Quit;
+            }
+            if is_key_down(&event, VirtualKeyCode::Escape) {
+                return Trans::Push(Box::new(super::Pause::default()));
             }
 
             // Listen to any key events
             if let Some(event) = get_key(&event) {
                 match event {
-                    (VirtualKeyCode::R, ElementState::Pressed) => {
-                        return Trans::Switch(Box::new(PlayingMap::default()));
+                    (VirtualKeyCode::T, ElementState::Pressed) => {
+                        self.next_theme(data.world);
                     }
-                    (VirtualKeyCode::Add, ElementState::Pressed)
-                    | (VirtualKeyCode::Equals, ElementState::Pressed) => {
-                        let zoom = data.world.read_resource::<camera::ZoomLevel>().clone().up();
-                        data.world.insert(zoom);
+                    (VirtualKeyCode::F1, ElementState::Pressed) => {
+                        return Trans::Push(Box::new(super::Settings::default()));
                     }
-                    (VirtualKeyCode::Subtract, ElementState::Pressed) => {
-                        let zoom = data
-                            .world
-                            .read_resource::<camera::ZoomLevel>()
-                            .clone()
-                            .down();
-                        data.world.insert(zoom);
-                    }
-                    event => self.input_tracker.handle_key(event),
+                    _ => {}
                 }
             }
+        }
 
-            // If you're looking for a more sophisticated event handling solution,
-            // including key bindings and gamepad support, please have a look at
-            // https://book.amethyst.rs/stable/pong-tutorial/pong-tutorial-03.html#capturing-user-input
+        // everything else comes from the actions of `config/bindings.ron`
+        // (or the player's own), and from the left stick of any controller
+        match &event {
+            StateEvent::Input(InputEvent::ActionPressed(action)) => match action.as_str() {
+                // asks first, a whole cave is easy to lose by accident
+                "restart" => return Trans::Push(Box::new(super::Pause::restarting())),
+                "zoom_in" => {
+                    let zoom = data.world.read_resource::<camera::ZoomLevel>().clone().up();
+                    data.world.insert(zoom);
+                }
+                "zoom_out" => {
+                    let zoom = data
+                        .world
+                        .read_resource::<camera::ZoomLevel>()
+                        .clone()
+                        .down();
+                    data.world.insert(zoom);
+                }
+                action => self.input_tracker.handle_action(action, true),
+            },
+            StateEvent::Input(InputEvent::ActionReleased(action)) => {
+                // another key bound to the same action may still be held
+                let still_down = data
+                    .world
+                    .read_resource::<InputHandler<StringBindings>>()
+                    .action_is_down(action)
+                    .unwrap_or(false);
+                if !still_down {
+                    self.input_tracker.handle_action(action, false);
+                }
+            }
+            StateEvent::Input(InputEvent::ControllerAxisMoved { axis, value, .. }) => match axis {
+                ControllerAxis::LeftX => self
+                    .input_tracker
+                    .handle_stick(input::StickAxis::Horizontal, *value),
+                // controllers have y going down
+                ControllerAxis::LeftY => self
+                    .input_tracker
+                    .handle_stick(input::StickAxis::Vertical, -*value),
+                _ => {}
+            },
+            _ => {}
         }
 
         // Keep going
@@ -130,8 +320,171 @@ impl Component for GridSprite {
 }
 
 impl<'a, 'b> PlayingMap<'a, 'b> {
-    fn clear_grid(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) {
-        let world = &mut data.world;
+    /// The cave on its own, played over for as long as the player likes
+    pub fn new(cave: Cave) -> Self {
+        PlayingMap {
+            cave,
+            level: 1,
+            in_session: false,
+            recorded: false,
+            over_for: 0,
+            name_entry: None,
+            leaving: None,
+            dispatcher: None,
+            camera: None,
+            sprites: None,
+            sprite_scale: 1.,
+            tick_count: 0,
+            input_tracker: Default::default(),
+            replay: Default::default(),
+            playback: None,
+            visible_tiles: HashMap::new(),
+            visible_cells: (0..0, 0..0),
+            spare_entities: vec![],
+            pause_choice: None,
+        }
+    }
+
+    /// The cave as part of a `GameSession`, on its difficulty `level`
+    pub fn in_session(cave: Cave, level: u32) -> Self {
+        PlayingMap {
+            level,
+            in_session: true,
+            ..PlayingMap::new(cave)
+        }
+    }
+
+    /// `replay` played back in the cave, which goes into no records
+    pub fn replaying(cave: Cave, replay: replay::Replay) -> Self {
+        PlayingMap {
+            recorded: true,
+            playback: Some(replay),
+            ..PlayingMap::new(cave)
+        }
+    }
+
+    // in a game on lives, starting over costs a life like giving up does
+    fn restart(&mut self, world: &mut World) -> SimpleTrans {
+        if self.in_session {
+            world.write_resource::<grid::GridState>().give_up();
+            Trans::None
+        } else {
+            self.leave(world, Leave::Restart)
+        }
+    }
+
+    // only a cave that ended counts, not one left halfway; a high score
+    // made in it still gets its name asked for first
+    fn leave(&mut self, world: &mut World, leave: Leave) -> SimpleTrans {
+        if let Some(name_entry) = self.name_entry.take() {
+            self.leaving = Some(leave);
+            return Trans::Push(Box::new(name_entry));
+        }
+
+        match leave {
+            Leave::Restart => {
+                let cave = self.cave.clone();
+                Trans::Switch(Box::new(match self.playback.clone() {
+                    Some(playback) => PlayingMap::replaying(cave, playback),
+                    None => PlayingMap::new(cave),
+                }))
+            }
+            Leave::Quit => {
+                if self.in_session {
+                    world.insert(CaveResult {
+                        end: CaveEnd::Quit,
+                        score: 0,
+                    });
+                }
+                Trans::Pop
+            }
+        }
+    }
+
+    // keep the score and time if they beat the best ones of the cave,
+    // and ask for a name if the score made the high scores
+    fn record_result(&mut self, grid: &grid::GridState) -> Option<super::NameEntry> {
+        self.recorded = true;
+        let score = game::score(grid);
+
+        let key = TableKey {
+            pack: self.cave.pack.clone(),
+            cave: self.cave.id.clone(),
+            level: self.level,
+        };
+        if let Some(path) = records::user_path() {
+            let result = records::Records::load(&path).and_then(|mut records| {
+                records.record(
+                    key.clone(),
+                    score,
+                    grid.tick_count,
+                    grid.outcome() == grid::Outcome::Completed,
+                );
+                records.save(&path)
+            });
+            if let Err(e) = result {
+                log::warn!("Failed to save records: {}", e);
+            }
+        }
+
+        let high_scores = highscores::user_path()?;
+        let high_scores = HighScores::load(high_scores)
+            .map_err(|e| log::warn!("Failed to load high scores: {}", e))
+            .ok()?;
+        if !high_scores.qualifies(&key, score) {
+            return None;
+        }
+        let entry = highscores::Entry {
+            name: String::new(),
+            score,
+            ticks: grid.tick_count,
+            diamonds: grid.diamond_count,
+        };
+        Some(super::NameEntry::new(key, entry, self.cave.name()))
+    }
+
+    fn load_theme(&mut self, world: &mut World) {
+        let name = world.read_resource::<theme::SelectedTheme>().0.clone();
+        let theme = theme::Theme::load(&name).unwrap_or_else(|e| {
+            log::warn!("Failed to load theme {}: {}", name, e);
+            theme::Theme::default()
+        });
+        let palette = world.read_resource::<grid::GridState>().params().palette;
+
+        // hi-res themes are drawn as big as the others
+        self.sprite_scale = match theme::sprite_rects(&name).map(|rects| theme::tile_size(&rects)) {
+            Ok(0) => 1.,
+            Ok(size) => crate::TILE_SIZE / size as f32,
+            Err(e) => {
+                log::warn!("Failed to read sprite positions of theme {}: {}", name, e);
+                1.
+            }
+        };
+        self.sprites = Some(super::load_sprites(world, &name, &theme, palette));
+        world.insert(theme);
+    }
+
+    fn next_theme(&mut self, world: &mut World) {
+        let themes = theme::list().unwrap_or_else(|e| {
+            log::warn!("Failed to list themes: {}", e);
+            vec![]
+        });
+        let current = world.read_resource::<theme::SelectedTheme>().0.clone();
+        let next = match themes.iter().position(|name| *name == current) {
+            Some(i) => themes[(i + 1) % themes.len()].clone(),
+            None => match themes.first() {
+                Some(name) => name.clone(),
+                None => return,
+            },
+        };
+
+        world.insert(theme::SelectedTheme(next));
+        // every visible tile gets a new sprite on the next redraw
+        self.clear_grid(world);
+        self.load_theme(world);
+    }
+
+    fn clear_grid(&mut self, world: &mut World) {
         {
             let entities = world.entities();
 
@@ -140,39 +493,182 @@ impl<'a, 'b> PlayingMap<'a, 'b> {
             }
         }
         world.maintain();
+        self.visible_tiles.clear();
+        self.spare_entities.clear();
+        self.visible_cells = (0..0, 0..0);
     }
 
-    fn redraw_grid(&mut self, mut data: StateData<'_, GameData<'_, '_>>) {
-        self.clear_grid(&mut data);
-        // TODO: lame clone
-        let grid = (*data.world.read_resource::<crate::grid::GridState>()).clone();
-
-        // TODO: we don't need to draw all sprites - only the ones that are near
-        // TODO: maybe reusing entities is faster?
-        for i in 0..(grid.width() * grid.height()) {
-            let pos = grid::GridPos::new(i);
-            let tile = grid.get_tile(pos);
-            if let Some(sprite_number) = tile.to_sprite_number() {
-                let sprite_render = SpriteRender {
-                    sprite_sheet: self.sprites.clone().expect("sprites already loaded"),
-                    sprite_number,
+    // keeps sprites for the cells in view; `ticked` also shows what the
+    // last tick changed
+    fn redraw_grid(&mut self, world: &mut World, ticked: bool) {
+        let sprite_sheet = self.sprites.clone().expect("sprites already loaded");
+        let (
+            entities,
+            grid,
+            theme,
+            view,
+            mut sprite_renders,
+            mut transforms,
+            mut grid_sprites,
+            mut motions,
+            mut animated,
+        ): (
+            Entities,
+            ReadExpect<grid::GridState>,
+            ReadExpect<theme::Theme>,
+            ReadExpect<camera::CameraView>,
+            WriteStorage<SpriteRender>,
+            WriteStorage<Transform>,
+            WriteStorage<GridSprite>,
+            WriteStorage<motion::TileMotion>,
+            WriteStorage<animation::Animated>,
+        ) = world.system_data();
+        let width = grid.width();
+
+        // whatever was sliding during the previous tick is in place by now
+        if ticked {
+            for (motion, transform) in (&motions, &mut transforms).join() {
+                transform.set_translation(motion.to);
+            }
+            motions.clear();
+        }
+
+        // return the entities of cells that went out of view to the pool
+        let (xs, ys) = view.cells(VIEW_MARGIN, width, grid.height());
+        self.visible_cells = (xs.clone(), ys.clone());
+        let in_view = |i: usize| {
+            let (x, y) = grid::GridPos::new(i).to_xy(width);
+            xs.contains(&x) && ys.contains(&y)
+        };
+        let spare_entities = &mut self.spare_entities;
+        self.visible_tiles.retain(|i, tile| {
+            if in_view(*i) {
+                return true;
+            }
+            sprite_renders.remove(tile.entity);
+            animated.remove(tile.entity);
+            // it may still be sliding, when the camera moved between ticks
+            motions.remove(tile.entity);
+            spare_entities.push(tile.entity);
+            false
+        });
+
+        // cells that just came into view need a look, the rest
+        // only if the last tick reported them as changed
+        let events: &[grid::GridEvent] = if ticked { &grid.events } else { &[] };
+        let mut dirty: Vec<usize> = events
+            .iter()
+            .flat_map(|event| event.positions())
+            .map(grid::GridPos::index)
+            .filter(|i| self.visible_tiles.contains_key(i))
+            .collect();
+        // so the player goes back to standing still when not walking anymore
+        if ticked && self.visible_tiles.contains_key(&grid.player_pos.index()) {
+            dirty.push(grid.player_pos.index());
+        }
+
+        for y in ys.clone() {
+            for x in xs.clone() {
+                let pos = grid::GridPos::from_xy(x, y, width);
+                if self.visible_tiles.contains_key(&pos.index()) {
+                    continue;
+                }
+
+                let entity = match self.spare_entities.pop() {
+                    Some(entity) => entity,
+                    None => {
+                        let mut transform = Transform::default();
+                        transform.set_scale(Vector3::repeat(self.sprite_scale));
+                        entities
+                            .build_entity()
+                            .with(transform, &mut transforms)
+                            .with(GridSprite, &mut grid_sprites)
+                            .build()
+                    }
                 };
-                let (x, y) = pos.to_xy(grid.width());
-
-                let mut transform = Transform::default();
-                transform.set_translation(Vector3::new(
-                    x as f32 * crate::TILE_SIZE,
-                    y as f32 * crate::TILE_SIZE,
-                    0.,
-                ));
-                let _entity = data
-                    .world
-                    .create_entity()
-                    .with(sprite_render)
-                    .with(transform)
-                    .with(GridSprite)
-                    .build();
+                if let Some(transform) = transforms.get_mut(entity) {
+                    transform.set_translation(to_translation(pos, width));
+                }
+                self.visible_tiles.insert(
+                    pos.index(),
+                    VisibleTile {
+                        entity,
+                        look: (None, None),
+                    },
+                );
+                dirty.push(pos.index());
+            }
+        }
+
+        // if a tile moved more than once in a tick, slide it all the way
+        // from where it was at the beginning
+        let mut moved_from: Vec<(grid::GridPos, grid::GridPos)> = vec![];
+        for event in events {
+            if let grid::GridEvent::Moved { from, to, .. } = *event {
+                let origin = match moved_from.iter().position(|(_, dst)| *dst == from) {
+                    Some(i) => moved_from.remove(i).0,
+                    None => from,
+                };
+                moved_from.push((origin, to));
+            }
+        }
+        for (from, to) in moved_from {
+            if let Some(tile) = self.visible_tiles.get(&to.index()) {
+                motions
+                    .insert(
+                        tile.entity,
+                        motion::TileMotion {
+                            from: to_translation(from, width),
+                            to: to_translation(to, width),
+                        },
+                    )
+                    .expect("tile entity is alive");
+            }
+        }
+
+        let walked = grid.player_walked();
+
+        for i in dirty {
+            let tile = grid.tiles()[i];
+            let sprite_number = theme.sprite_for(tile);
+            let animation = theme.animation_for(tile, walked);
+
+            let visible = self
+                .visible_tiles
+                .get_mut(&i)
+                .expect("dirty tiles are visible");
+            if visible.look == (sprite_number, animation) {
+                continue;
+            }
+            visible.look = (sprite_number, animation);
+
+            let entity = visible.entity;
+            if let Some(sprite_number) = sprite_number {
+                sprite_renders
+                    .insert(
+                        entity,
+                        SpriteRender {
+                            sprite_sheet: sprite_sheet.clone(),
+                            sprite_number,
+                        },
+                    )
+                    .expect("tile entity is alive");
+            } else {
+                sprite_renders.remove(entity);
+            }
+
+            if let Some(animation) = animation {
+                animated
+                    .insert(entity, animation::Animated(animation))
+                    .expect("tile entity is alive");
+            } else {
+                animated.remove(entity);
             }
         }
     }
 }
+
+fn to_translation(pos: grid::GridPos, width: usize) -> Vector3<f32> {
+    let (x, y) = pos.to_xy(width);
+    Vector3::new(x as f32 * crate::TILE_SIZE, y as f32 * crate::TILE_SIZE, 0.)
+}
@@ -0,0 +1,190 @@
+use amethyst::{
+    assets::Loader,
+    ecs::prelude::*,
+    input::{
+        get_key, is_close_requested, Button, ElementState, InputHandler, StringBindings,
+        VirtualKeyCode,
+    },
+    prelude::*,
+    ui::{Anchor, TtfFormat, UiText, UiTransform},
+};
+
+use std::path::Path;
+
+use super::menu::Menu;
+use crate::bindings;
+
+/// Screen for rebinding the controls: pick an action, then press its new key
+pub struct Settings {
+    menu: Menu,
+    help: Option<Entity>,
+    // the selected action gets the next key pressed
+    waiting: bool,
+    // keys that were down already when the waiting began, like the one
+    // that picked the action; they don't count until let go
+    held: Vec<VirtualKeyCode>,
+    // why the last key pressed couldn't be bound
+    refused: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let items: Vec<(&str, bool)> = bindings::ACTIONS
+            .iter()
+            .map(|(_, name)| (*name, true))
+            .collect();
+        Settings {
+            menu: Menu::new("Controls", &items),
+            help: None,
+            waiting: false,
+            held: vec![],
+            refused: None,
+        }
+    }
+}
+
+impl SimpleState for Settings {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        self.menu.show(world);
+
+        let font = world.read_resource::<Loader>().load(
+            "font/bd.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+        // under the menu, which has the title and then a line per action
+        let y = -160. - 60. * bindings::ACTIONS.len() as f32;
+        let help = world
+            .create_entity()
+            .with(UiTransform::new(
+                "settings_help".to_string(),
+                Anchor::TopMiddle,
+                Anchor::TopMiddle,
+                0.,
+                y,
+                1.,
+                900.,
+                35.,
+            ))
+            .with(UiText::new(font, String::new(), [0.6, 0.6, 0.6, 1.], 25.))
+            .build();
+        self.help = Some(help);
+
+        self.refresh(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.menu.hide(data.world);
+        if let Some(help) = self.help.take() {
+            data.world
+                .delete_entity(help)
+                .expect("settings help is alive");
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(&event) {
+                return Trans::Quit;
+            }
+        }
+
+        // the key to bind is taken as it is, whatever it's bound to now
+        if self.waiting {
+            if let StateEvent::Window(event) = &event {
+                match get_key(&event) {
+                    Some((key, ElementState::Released)) => self.held.retain(|held| *held != key),
+                    Some((key, ElementState::Pressed)) if !self.held.contains(&key) => {
+                        self.waiting = false;
+                        if key != VirtualKeyCode::Escape {
+                            self.rebind(data.world, key);
+                        }
+                        self.refresh(data.world);
+                    }
+                    _ => {}
+                }
+            }
+            return Trans::None;
+        }
+
+        if let StateEvent::Window(event) = &event {
+            if let Some((VirtualKeyCode::Escape, ElementState::Pressed)) = get_key(&event) {
+                return Trans::Pop;
+            }
+        }
+        if self.menu.handle_event(data.world, &event).is_some() {
+            self.waiting = true;
+            self.refused = None;
+            self.held = data
+                .world
+                .read_resource::<InputHandler<StringBindings>>()
+                .keys_that_are_down()
+                .collect();
+            self.refresh(data.world);
+        }
+
+        Trans::None
+    }
+}
+
+impl Settings {
+    fn rebind(&mut self, world: &mut World, key: VirtualKeyCode) {
+        let (action, _) = bindings::ACTIONS[self.menu.selected()];
+        let mut input = world.write_resource::<InputHandler<StringBindings>>();
+        let mut new_bindings = input.bindings.clone();
+        if let Err(e) = bindings::rebind(&mut new_bindings, action, Button::Key(key)) {
+            self.refused = Some(e.to_string());
+            return;
+        }
+
+        if let Err(e) = bindings::save_user(&new_bindings, Path::new(bindings::DEFAULT_BINDINGS)) {
+            log::warn!("Failed to save bindings: {}", e);
+        }
+        input.bindings = new_bindings;
+    }
+
+    fn refresh(&mut self, world: &World) {
+        if let Some(text) = self
+            .help
+            .and_then(|help| world.write_storage::<UiText>().get_mut(help))
+        {
+            text.text = if let Some(refused) = &self.refused {
+                format!("{}, pick another key", refused)
+            } else if self.waiting {
+                "Press the new key, Esc to keep the old one".to_string()
+            } else {
+                "Up/Down to pick, Enter or fire to change, Esc to go back".to_string()
+            };
+        }
+
+        let labels: Vec<String> = {
+            let input = world.read_resource::<InputHandler<StringBindings>>();
+            bindings::ACTIONS
+                .iter()
+                .enumerate()
+                .map(|(i, (action, name))| {
+                    let keys = if self.waiting && i == self.menu.selected() {
+                        "...".to_string()
+                    } else {
+                        let keys: Vec<String> = input
+                            .bindings
+                            .action_bindings(*action)
+                            .map(bindings::describe)
+                            .collect();
+                        keys.join(", ")
+                    };
+                    format!("{}: {}", name, keys)
+                })
+                .collect()
+        };
+        for (i, label) in labels.into_iter().enumerate() {
+            self.menu.set_label(world, i, label);
+        }
+    }
+}
@@ -1,2 +1,4 @@
+pub mod animation;
 pub mod camera;
+pub mod motion;
 pub mod score;
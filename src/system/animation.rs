@@ -0,0 +1,39 @@
+use amethyst::{
+    core::{SystemDesc, Time},
+    derive::SystemDesc,
+    ecs::{prelude::*, Read, ReadExpect, ReadStorage, System, SystemData, World, WriteStorage},
+    renderer::SpriteRender,
+};
+
+use crate::theme::Theme;
+
+/// Sprite that cycles through the frames of the named animation of the theme
+#[derive(Debug, Copy, Clone)]
+pub struct Animated(pub &'static str);
+
+impl Component for Animated {
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[derive(SystemDesc, Debug, Default)]
+pub struct AnimationSystem;
+
+impl<'s> System<'s> for AnimationSystem {
+    type SystemData = (
+        ReadStorage<'s, Animated>,
+        WriteStorage<'s, SpriteRender>,
+        Read<'s, Time>,
+        ReadExpect<'s, Theme>,
+    );
+
+    fn run(&mut self, (animated, mut sprite_renders, time, theme): Self::SystemData) {
+        // frames advance with the wall clock, not with the grid simulation
+        let time = time.absolute_real_time_seconds();
+
+        for (animated, sprite_render) in (&animated, &mut sprite_renders).join() {
+            if let Some(sprite_number) = theme.frame_at(animated.0, time) {
+                sprite_render.sprite_number = sprite_number;
+            }
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use amethyst::{
     core::{transform::Transform, SystemDesc, Time},
     derive::SystemDesc,
-    ecs::{prelude::*, Read, System, SystemData, World, WriteStorage},
+    ecs::{prelude::*, Read, System, SystemData, World, Write, WriteStorage},
     renderer::Camera,
     window::ScreenDimensions,
 };
@@ -30,6 +30,35 @@ impl ZoomLevel {
     }
 }
 
+/// The part of the world (in world coordinates) the camera is looking at
+#[derive(Debug, Copy, Clone, Default)]
+pub struct CameraView {
+    pub left: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub top: f32,
+}
+
+impl CameraView {
+    /// Ranges of grid columns and rows in view, extended by `margin` cells
+    /// on every side and limited to the grid size
+    pub fn cells(
+        &self,
+        margin: usize,
+        width: usize,
+        height: usize,
+    ) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+        let to_cell = |v: f32| (v / TILE_SIZE).round().max(0.) as usize;
+
+        let min_x = to_cell(self.left).saturating_sub(margin).min(width);
+        let max_x = (to_cell(self.right) + margin + 1).min(width);
+        let min_y = to_cell(self.bottom).saturating_sub(margin).min(height);
+        let max_y = (to_cell(self.top) + margin + 1).min(height);
+
+        (min_x..max_x, min_y..max_y)
+    }
+}
+
 #[derive(SystemDesc, Debug, Default)]
 pub struct CameraSystem {
     current_x: f32,
@@ -37,7 +66,8 @@ pub struct CameraSystem {
 }
 
 impl CameraSystem {
-    pub fn init(world: &mut World, dimensions: &ScreenDimensions) {
+    /// Create the camera, which is up to the caller to delete again
+    pub fn init(world: &mut World, dimensions: &ScreenDimensions) -> Entity {
         let camera_x = dimensions.width();
         let camera_y = dimensions.height();
 
@@ -46,15 +76,17 @@ impl CameraSystem {
         let transform = Transform::default();
         // transform.set_translation_xyz(camera_x * 0.5, camera_y * 0.5, 1.);
 
-        world
+        let camera = world
             .create_entity()
             .with(Camera::standard_2d(camera_x, camera_y))
             .with(transform)
             .build();
 
         world.insert(ZoomLevel::new());
+        world.insert(CameraView::default());
 
         Self::update_screen_dimensions(world);
+        camera
     }
 
     pub fn update_screen_dimensions(world: &mut World) {
@@ -83,11 +115,12 @@ impl<'s> System<'s> for CameraSystem {
         Read<'s, grid::GridState>,
         Read<'s, Option<ScreenDimensions>>,
         Read<'s, ZoomLevel>,
+        Write<'s, CameraView>,
     );
 
     fn run(
         &mut self,
-        (mut camera, mut transform, time, grid_map_state, screen_dimensions, zoom_level): Self::SystemData,
+        (mut camera, mut transform, time, grid_map_state, screen_dimensions, zoom_level, mut view): Self::SystemData,
     ) {
         let screen_dimensions = screen_dimensions.as_ref().expect("screen dimensions set");
 
@@ -126,5 +159,12 @@ impl<'s> System<'s> for CameraSystem {
             self.current_y += dy;
             transform.set_translation_xyz(self.current_x.round(), self.current_y.round(), 1.0);
         }
+
+        *view = CameraView {
+            left: self.current_x - padding_x,
+            right: self.current_x + padding_x,
+            bottom: self.current_y - padding_y,
+            top: self.current_y + padding_y,
+        };
     }
 }
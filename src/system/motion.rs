@@ -0,0 +1,38 @@
+use amethyst::{
+    core::{math::Vector3, transform::Transform, SystemDesc},
+    derive::SystemDesc,
+    ecs::{prelude::*, Read, ReadStorage, System, SystemData, World, WriteStorage},
+};
+
+/// How far (`0.0` - `1.0`) we are between the last simulation tick and the next one
+#[derive(Debug, Copy, Clone, Default)]
+pub struct TickProgress(pub f32);
+
+/// A tile sprite sliding from its previous grid position to the new one
+#[derive(Debug, Copy, Clone)]
+pub struct TileMotion {
+    pub from: Vector3<f32>,
+    pub to: Vector3<f32>,
+}
+
+impl Component for TileMotion {
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[derive(SystemDesc, Debug, Default)]
+pub struct MotionSystem;
+
+impl<'s> System<'s> for MotionSystem {
+    type SystemData = (
+        ReadStorage<'s, TileMotion>,
+        WriteStorage<'s, Transform>,
+        Read<'s, TickProgress>,
+    );
+
+    fn run(&mut self, (motions, mut transforms, progress): Self::SystemData) {
+        let progress = progress.0.max(0.).min(1.);
+        for (motion, transform) in (&motions, &mut transforms).join() {
+            transform.set_translation(motion.from.lerp(&motion.to, progress));
+        }
+    }
+}
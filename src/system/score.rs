@@ -6,13 +6,12 @@ use amethyst::{
     ui::{Anchor, TtfFormat, UiText, UiTransform},
 };
 
-use std::time::Instant;
-
-use crate::grid;
+use crate::{game, grid, session::Session};
 
 pub struct Scoreboard {
     pub score: Entity,
     pub time: Entity,
+    pub lives: Entity,
 }
 
 pub fn initialise_scoreboard(world: &mut World) {
@@ -64,7 +63,27 @@ pub fn initialise_scoreboard(world: &mut World) {
         ))
         .build();
 
-    world.insert(Scoreboard { time, score });
+    let lives = world
+        .create_entity()
+        .with(UiTransform::new(
+            "Lives".to_string(),
+            Anchor::TopMiddle,
+            Anchor::TopMiddle,
+            -50.,
+            -10.,
+            1.,
+            200.,
+            50.,
+        ))
+        .with(UiText::new(
+            font_handle.clone(),
+            String::new(),
+            [1., 1., 1., 1.],
+            50.,
+        ))
+        .build();
+
+    world.insert(Scoreboard { time, score, lives });
 }
 
 pub fn clear_scoreboard(world: &World) {
@@ -76,40 +95,42 @@ pub fn clear_scoreboard(world: &World) {
     entities
         .delete(scoreboard.time)
         .expect("Unable to delete scoreboard time");
+    entities
+        .delete(scoreboard.lives)
+        .expect("Unable to delete scoreboard lives");
 }
 
-#[derive(SystemDesc)]
-pub struct ScoreSystem {
-    start_time: Instant,
-}
-
-impl Default for ScoreSystem {
-    fn default() -> Self {
-        ScoreSystem {
-            start_time: Instant::now(),
-        }
-    }
-}
+#[derive(SystemDesc, Default)]
+pub struct ScoreSystem;
 
 impl<'s> System<'s> for ScoreSystem {
     type SystemData = (
         WriteStorage<'s, UiText>,
         Read<'s, grid::GridState>,
         ReadExpect<'s, Scoreboard>,
+        Option<Read<'s, Session>>,
     );
 
-    fn run(&mut self, (mut ui_text, grid_map_state, score_text): Self::SystemData) {
-        let time_elapsed = self.start_time.elapsed().as_secs();
+    fn run(&mut self, (mut ui_text, grid_map_state, score_text, session): Self::SystemData) {
+        // time of the cave, so it stands still whenever the grid does;
+        // counting down if the cave has a time limit
+        let time = game::time_left(&grid_map_state)
+            .unwrap_or_else(|| game::cave_time(grid_map_state.tick_count))
+            .as_secs();
+        // in a game on lives, the caves before this one count too
+        let score_before = session.as_ref().map_or(0, |session| session.score);
         if let Some(text) = ui_text.get_mut(score_text.score) {
-            text.text = format!("{:0>6}", calculate_score(grid_map_state.diamond_count));
+            text.text = format!("{:0>6}", score_before + game::score(&grid_map_state));
         }
 
         if let Some(text) = ui_text.get_mut(score_text.time) {
-            text.text = time_elapsed.to_string();
+            text.text = time.to_string();
         }
-    }
-}
 
-fn calculate_score(diamond_count: usize) -> usize {
-    return diamond_count * 5;
+        if let Some(text) = ui_text.get_mut(score_text.lives) {
+            text.text = session
+                .as_ref()
+                .map_or(String::new(), |session| format!("Lives {}", session.lives));
+        }
+    }
 }
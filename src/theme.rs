@@ -0,0 +1,159 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    grid::TileType,
+    input::Direction,
+    palette::{Palette, Rgb, SpriteRect},
+};
+
+pub const THEMES_DIR: &str = "./resources/themes";
+pub const DEFAULT_THEME: &str = "classic";
+
+/// A tileset: `grid.png` + `grid.ron` sprite sheet, and `theme.ron`
+/// saying which sprites to use for what
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Theme {
+    // sprite numbers, keyed by the tile kind name
+    pub tiles: HashMap<String, usize>,
+    // how long every animation frame is displayed, in seconds
+    #[serde(default)]
+    pub frame_duration: f32,
+    // sprite numbers of animation frames, keyed by the animation name
+    #[serde(default)]
+    pub animations: HashMap<String, Vec<usize>>,
+}
+
+impl Theme {
+    pub fn load(name: &str) -> Result<Self> {
+        let path = dir(name).join("theme.ron");
+        Ok(ron::de::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn sprite_for(&self, tile: TileType) -> Option<usize> {
+        self.tiles.get(tile.kind_name()).copied()
+    }
+
+    pub fn frames(&self, animation: &str) -> Option<&[usize]> {
+        self.animations
+            .get(animation)
+            .map(Vec::as_slice)
+            .filter(|frames| !frames.is_empty())
+    }
+
+    /// Animation to show `tile` with, if the theme has one; `walked` is
+    /// where the player went during the last tick
+    pub fn animation_for(&self, tile: TileType, walked: Option<Direction>) -> Option<&'static str> {
+        let walking = match (tile, walked) {
+            (TileType::Player, Some(Direction::Left)) => Some("player_left"),
+            (TileType::Player, Some(Direction::Right)) => Some("player_right"),
+            _ => None,
+        };
+        walking
+            .filter(|name| self.frames(name).is_some())
+            .or_else(|| Some(tile.kind_name()).filter(|name| self.frames(name).is_some()))
+    }
+
+    /// Sprite of the named animation, `time` seconds into it
+    pub fn frame_at(&self, animation: &str, time: f64) -> Option<usize> {
+        let frames = self.frames(animation)?;
+        if self.frame_duration <= 0. {
+            return Some(frames[0]);
+        }
+        let frame = (time / f64::from(self.frame_duration)) as usize;
+        Some(frames[frame % frames.len()])
+    }
+
+    /// Parts of the sprite sheet of the named theme to paint with the cave
+    /// colours, and the colours
+    pub fn palette_regions(&self, name: &str, palette: Palette) -> Result<Vec<(SpriteRect, Rgb)>> {
+        let rects = sprite_rects(name)?;
+        let tiles = [
+            TileType::Dirt,
+            TileType::Wall,
+            TileType::Rock,
+            TileType::Steel,
+            TileType::Exit,
+        ];
+        Ok(tiles
+            .iter()
+            .filter_map(|tile| {
+                let color = palette.color_for(*tile)?;
+                let rect = rects.get(self.sprite_for(*tile)?)?;
+                Some((*rect, color))
+            })
+            .collect())
+    }
+}
+
+/// Directory of the named theme
+pub fn dir(name: &str) -> PathBuf {
+    Path::new(THEMES_DIR).join(name)
+}
+
+/// Names of all the themes available, sorted
+pub fn list() -> Result<Vec<String>> {
+    let mut names = vec![];
+    for entry in fs::read_dir(THEMES_DIR)? {
+        let path = entry?.path();
+        if path.join("theme.ron").is_file() {
+            if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+#[derive(Deserialize)]
+struct SpriteList {
+    sprites: Vec<SpritePosition>,
+}
+
+#[derive(Deserialize)]
+struct SpritePosition {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+/// Positions of the sprites in the sheet of the named theme, from its `grid.ron`
+pub fn sprite_rects(name: &str) -> Result<Vec<SpriteRect>> {
+    let list: SpriteList = ron::de::from_str(&fs::read_to_string(dir(name).join("grid.ron"))?)?;
+    Ok(list
+        .sprites
+        .into_iter()
+        .map(|s| SpriteRect {
+            x: s.x,
+            y: s.y,
+            width: s.width,
+            height: s.height,
+        })
+        .collect())
+}
+
+/// Size of a grid cell for a sprite sheet: the biggest sprite fits in it
+pub fn tile_size(rects: &[SpriteRect]) -> usize {
+    rects
+        .iter()
+        .map(|rect| rect.width.max(rect.height))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Name of the theme the player picked
+#[derive(Debug, Clone)]
+pub struct SelectedTheme(pub String);
+
+impl Default for SelectedTheme {
+    fn default() -> Self {
+        SelectedTheme(DEFAULT_THEME.to_string())
+    }
+}
@@ -0,0 +1,192 @@
+use anyhow::Result;
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute, queue,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use rand::{thread_rng, Rng};
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    time::Instant,
+};
+
+use crate::{
+    game,
+    grid::{CreatureKind, GridPos, GridState, TileType},
+    input::{Direction, InputConfig, InputTracker, INPUT_CONFIG},
+    map::MapDescription,
+    palette::{Palette, Rgb},
+    replay::Replay,
+};
+
+/// Play a cave in the terminal, no window or GPU needed
+pub fn run(map_path: Option<PathBuf>) -> Result<()> {
+    let mut stdout = io::stdout();
+    let _screen = Screen::enter(&mut stdout)?;
+    play(&mut stdout, map_path)
+}
+
+// the terminal taken over for the game; it's given back when this is
+// dropped, even on errors and panics
+struct Screen;
+
+impl Screen {
+    fn enter(out: &mut impl Write) -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        let screen = Screen;
+        execute!(out, EnterAlternateScreen, Hide)?;
+        Ok(screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), ResetColor, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn load_grid(map_path: &Option<PathBuf>) -> Result<GridState> {
+    Ok(match map_path {
+        Some(path) => GridState::from_map(MapDescription::load(path.clone())?, thread_rng().gen()),
+        None => GridState::new(),
+    })
+}
+
+fn play(out: &mut impl Write, map_path: Option<PathBuf>) -> Result<()> {
+    let input_config = InputConfig::load(INPUT_CONFIG).unwrap_or_default();
+    loop {
+        let mut grid = load_grid(&map_path)?;
+        let mut input = InputTracker::new(input_config.buffer_mode);
+        let mut replay = Replay::new(grid.seed());
+        replay.cave = map_path.clone();
+
+        let mut next_tick = Instant::now() + game::tick_duration();
+        draw(out, &grid)?;
+        let restart = loop {
+            let timeout = next_tick.saturating_duration_since(Instant::now());
+            if event::poll(timeout)? {
+                match event::read()? {
+                    Event::Key(KeyEvent { code, modifiers }) => {
+                        // terminals don't report modifier keys on their own,
+                        // so fire is held together with the arrow
+                        let fire =
+                            modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::SHIFT);
+                        match code {
+                            KeyCode::Esc | KeyCode::Char('q') => break false,
+                            KeyCode::Char('r') => break true,
+                            KeyCode::Left => input.press(Direction::Left, fire),
+                            KeyCode::Right => input.press(Direction::Right, fire),
+                            KeyCode::Up => input.press(Direction::Up, fire),
+                            KeyCode::Down => input.press(Direction::Down, fire),
+                            _ => {}
+                        }
+                    }
+                    Event::Resize(..) => draw(out, &grid)?,
+                    _ => {}
+                }
+            }
+
+            if Instant::now() >= next_tick {
+                game::run_tick(&mut grid, &mut input, &mut replay);
+                next_tick += game::tick_duration();
+                draw(out, &grid)?;
+            }
+        };
+
+        game::save_last_replay(&replay)?;
+        if !restart {
+            return Ok(());
+        }
+    }
+}
+
+// same characters as in the map files
+fn tile_char(tile: TileType) -> char {
+    use TileType::*;
+    match tile {
+        Empty => ' ',
+        Player => 's',
+        Dirt => '.',
+        Rock => 'o',
+        Wall => '%',
+        Diamond => '*',
+        Steel => '#',
+        Exit => 'X',
+        Creature {
+            kind: CreatureKind::Firefly,
+            ..
+        } => 'x',
+        Creature {
+            kind: CreatureKind::Butterfly,
+            ..
+        } => 'b',
+    }
+}
+
+fn tile_color(tile: TileType, palette: &Palette) -> Color {
+    let rgb = |[r, g, b]: Rgb| Color::Rgb { r, g, b };
+    match tile {
+        TileType::Player => Color::Yellow,
+        TileType::Diamond => Color::Cyan,
+        TileType::Creature { .. } => Color::Magenta,
+        // the colours of the sprite sheet, unless the cave has its own
+        TileType::Dirt => palette.dirt.map_or(Color::DarkYellow, rgb),
+        TileType::Wall => palette.wall.map_or(Color::Grey, rgb),
+        tile => palette.color_for(tile).map_or(Color::White, rgb),
+    }
+}
+
+// the first of `len` cells to show out of `total`, keeping `center` in the middle
+fn view_start(center: usize, len: usize, total: usize) -> usize {
+    center
+        .saturating_sub(len / 2)
+        .min(total.saturating_sub(len))
+}
+
+fn draw(out: &mut impl Write, grid: &GridState) -> Result<()> {
+    let (columns, rows) = terminal::size()?;
+    let columns = columns as usize;
+    // last row is the status line
+    let rows = (rows as usize).saturating_sub(1);
+    let palette = grid.params().palette.unwrap_or_default();
+
+    let (player_x, player_y) = grid.player_pos.to_xy(grid.width());
+    let start_x = view_start(player_x, columns, grid.width());
+    // the grid has y going up, the terminal down
+    let start_y = view_start(player_y, rows, grid.height());
+
+    queue!(out, Clear(ClearType::All))?;
+    for row in 0..rows.min(grid.height()) {
+        let y = start_y + rows.min(grid.height()) - 1 - row;
+        queue!(out, MoveTo(0, row as u16))?;
+        let mut color = None;
+        for x in start_x..(start_x + columns).min(grid.width()) {
+            let tile = grid.get_tile(GridPos::from_xy(x, y, grid.width()));
+            let tile_color = tile_color(tile, &palette);
+            if color != Some(tile_color) {
+                queue!(out, SetForegroundColor(tile_color))?;
+                color = Some(tile_color);
+            }
+            queue!(out, Print(tile_char(tile)))?;
+        }
+    }
+
+    queue!(
+        out,
+        MoveTo(0, rows as u16),
+        ResetColor,
+        Print(format!(
+            "score: {}  diamonds: {}  ticks: {}  {}  (arrows: move, ctrl/shift+arrow: fire, r: restart, q: quit)",
+            game::score(grid),
+            grid.diamond_count,
+            grid.tick_count,
+            game::describe_end(game::cave_end(grid)),
+        ))
+    )?;
+    out.flush()?;
+    Ok(())
+}
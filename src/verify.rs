@@ -0,0 +1,22 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::{game, grid::GridState, map::MapDescription, replay::Replay};
+
+/// Run a replay on a map without any window and print the final result
+pub fn run(map_path: PathBuf, replay_path: PathBuf) -> Result<()> {
+    let map = MapDescription::load(map_path)?;
+    let replay = Replay::load(replay_path)?;
+
+    let mut grid = GridState::from_map(map, replay.seed);
+    for actions in replay.ticks {
+        game::replay_tick(&mut grid, actions);
+    }
+
+    println!("score: {}", game::score(&grid));
+    println!("diamonds: {}", grid.diamond_count);
+    println!("ticks: {}", grid.tick_count);
+    println!("outcome: {}", game::describe_end(game::cave_end(&grid)));
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use bd::{
+    grid::{Outcome, TileType},
+    highscores::TableKey,
+    input::Direction,
+    map::{self, Cave, MapDescription},
+    records::{CaveRecord, Records},
+};
+use common::{key, walk};
+use std::path::PathBuf;
+
+#[test]
+fn map_header_names_cave() {
+    let map = MapDescription::from_reader(&b"name: Intro\ndiamonds_needed: 3\n###\n#s#\n###\n"[..])
+        .unwrap();
+    assert_eq!(map.params.name, Some("Intro".to_string()));
+    assert_eq!(map.params.diamonds_needed, 3);
+
+    assert!(MapDescription::from_reader(&b"diamonds_needed: lots\n###\n#s#\n###\n"[..]).is_err());
+}
+
+#[test]
+fn exit_opens_with_enough_diamonds() {
+    let mut grid = common::grid("diamonds_needed: 1\n######\n#s X*#\n######\n");
+    walk(&mut grid, Direction::Right, 3);
+    assert_eq!(grid.outcome(), Outcome::Playing);
+    assert_eq!(grid.player_pos.to_xy(grid.width()), (2, 1));
+
+    let mut grid = common::grid("diamonds_needed: 1\n######\n#s*X #\n######\n");
+    walk(&mut grid, Direction::Right, 2);
+    assert_eq!(grid.outcome(), Outcome::Completed);
+}
+
+#[test]
+fn edge_of_map_is_a_wall() {
+    let mut grid = common::grid("o \ns*\n");
+    for direction in &[Direction::Left, Direction::Down] {
+        walk(&mut grid, *direction, 1);
+        assert_eq!(grid.player_pos.to_xy(grid.width()), (0, 0));
+    }
+    walk(&mut grid, Direction::Right, 2);
+    assert_eq!(grid.player_pos.to_xy(grid.width()), (1, 0));
+    assert_eq!(grid.diamond_count, 1);
+}
+
+#[test]
+fn bundled_caves_load_in_order() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources/map");
+    let caves: Vec<Cave> = map::list(dir)
+        .unwrap()
+        .iter()
+        .map(|path| Cave::load(path).unwrap())
+        .collect();
+
+    let ids: Vec<&str> = caves.iter().map(|cave| cave.id.as_str()).collect();
+    assert_eq!(&ids[..2], &["01", "02"]);
+    for cave in &caves {
+        assert_ne!(cave.name(), cave.id, "cave {} has no name", cave.id);
+        let exits = cave
+            .map
+            .tiles
+            .iter()
+            .filter(|tile| **tile == TileType::Exit)
+            .count();
+        assert_eq!(exits, 1, "cave {} needs exactly one exit", cave.id);
+    }
+}
+
+#[test]
+fn records_keep_the_best() {
+    let mut records = Records::default();
+    records.record(key("01"), 10, 300, false);
+    assert_eq!(
+        records.get(&key("01")),
+        CaveRecord {
+            best_score: Some(10),
+            best_ticks: None,
+            completed: false,
+        }
+    );
+
+    records.record(key("01"), 60, 500, true);
+    records.record(key("01"), 20, 400, true);
+    assert_eq!(
+        records.get(&key("01")),
+        CaveRecord {
+            best_score: Some(60),
+            best_ticks: Some(400),
+            completed: true,
+        }
+    );
+    assert_eq!(records.get(&key("02")), CaveRecord::default());
+    // nor do other levels or packs share them
+    let harder = TableKey {
+        level: 2,
+        ..key("01")
+    };
+    assert_eq!(records.get(&harder), CaveRecord::default());
+    let other = TableKey {
+        pack: "other".to_string(),
+        ..key("01")
+    };
+    assert_eq!(records.get(&other), CaveRecord::default());
+}
+
+#[test]
+fn completing_a_cave_unlocks_the_next() {
+    let caves = [key("01"), key("02"), key("03")];
+    let mut records = Records::default();
+    assert!(records.unlocked(&caves, 0));
+    assert!(!records.unlocked(&caves, 1));
+
+    records.record(caves[0].clone(), 0, 100, false);
+    assert!(!records.unlocked(&caves, 1));
+    records.record(caves[0].clone(), 0, 100, true);
+    assert!(records.unlocked(&caves, 1));
+    assert!(!records.unlocked(&caves, 2));
+}
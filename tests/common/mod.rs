@@ -0,0 +1,33 @@
+// helpers shared by the tests; not every test uses all of them
+#![allow(dead_code)]
+
+use bd::{
+    grid::GridState,
+    highscores::TableKey,
+    input::{Action, Direction},
+    map::MapDescription,
+};
+
+/// Grid of a map given as text, with seed 0
+pub fn grid(text: &str) -> GridState {
+    GridState::from_map(MapDescription::from_reader(text.as_bytes()).unwrap(), 0)
+}
+
+/// Table of `cave` in the bundled pack, on the first level
+pub fn key(cave: &str) -> TableKey {
+    TableKey {
+        pack: "map".to_string(),
+        cave: cave.to_string(),
+        level: 1,
+    }
+}
+
+/// Run `steps` ticks moving in `direction`
+pub fn walk(grid: &mut GridState, direction: Direction, steps: usize) {
+    for _ in 0..steps {
+        grid.run_tick(vec![Action {
+            fire: false,
+            direction,
+        }]);
+    }
+}
@@ -0,0 +1,165 @@
+//! Recorded replays run against the bundled maps
+//!
+//! If any of these fail, the gameplay has changed: either fix the regression,
+//! or (if the change is intended) re-record the replay and update the expected
+//! values.
+use bd::{
+    game,
+    grid::{CreatureKind, GridState, TileType},
+    input::Direction,
+    map::MapDescription,
+    replay::Replay,
+    session::CaveEnd,
+};
+use std::path::PathBuf;
+
+struct Golden {
+    map: &'static str,
+    replay: &'static str,
+    tiles_hash: u64,
+    diamond_count: usize,
+    creatures_killed: usize,
+    tick_count: u64,
+    player_xy: (usize, usize),
+    end: Option<CaveEnd>,
+    score: usize,
+}
+
+fn path(rel: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(rel)
+}
+
+fn tile_bytes(tile: TileType) -> [u8; 3] {
+    use TileType::*;
+    match tile {
+        Empty => [0, 0, 0],
+        Player => [1, 0, 0],
+        Dirt => [2, 0, 0],
+        Rock => [3, 0, 0],
+        Wall => [4, 0, 0],
+        Diamond => [5, 0, 0],
+        Steel => [6, 0, 0],
+        Exit => [7, 0, 0],
+        Creature {
+            kind,
+            counter,
+            direction,
+        } => [
+            match kind {
+                CreatureKind::Firefly => 8,
+                CreatureKind::Butterfly => 9,
+            },
+            counter as u8,
+            match direction {
+                Direction::Up => 0,
+                Direction::Down => 1,
+                Direction::Left => 2,
+                Direction::Right => 3,
+            },
+        ],
+    }
+}
+
+// FNV-1a, so the expected values don't depend on std's hasher
+fn tiles_hash(grid: &GridState) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for tile in grid.tiles() {
+        for byte in tile_bytes(*tile).iter() {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+fn run_golden(golden: &Golden) {
+    let map = MapDescription::load(path(golden.map)).expect("map should load");
+    let replay = Replay::load(path(golden.replay)).expect("replay should load");
+
+    let mut grid = GridState::from_map(map, replay.seed);
+    for actions in replay.ticks {
+        game::replay_tick(&mut grid, actions);
+    }
+
+    assert_eq!(grid.tick_count, golden.tick_count, "{}", golden.replay);
+    assert_eq!(grid.diamond_count, golden.diamond_count, "{}", golden.replay);
+    assert_eq!(
+        grid.creatures_killed, golden.creatures_killed,
+        "{}",
+        golden.replay
+    );
+    assert_eq!(
+        grid.player_pos.to_xy(grid.width()),
+        golden.player_xy,
+        "{}",
+        golden.replay
+    );
+    assert_eq!(game::cave_end(&grid), golden.end, "{}", golden.replay);
+    assert_eq!(game::score(&grid), golden.score, "{}", golden.replay);
+    assert_eq!(tiles_hash(&grid), golden.tiles_hash, "{}", golden.replay);
+}
+
+#[test]
+fn map_01() {
+    run_golden(&Golden {
+        map: "resources/map/01.txt",
+        replay: "tests/replays/01.txt",
+        tiles_hash: 0x55e7d8ec6c2b7db9,
+        diamond_count: 10,
+        creatures_killed: 0,
+        tick_count: 65,
+        player_xy: (24, 1),
+        end: Some(CaveEnd::Completed),
+        score: 241,
+    });
+}
+
+// digs out the rock above the creature shaft on the right, which falls and
+// crushes the creature
+#[test]
+fn map_02() {
+    run_golden(&Golden {
+        map: "resources/map/02.txt",
+        replay: "tests/replays/02.txt",
+        tiles_hash: 0xeff2956ecd541287,
+        diamond_count: 20,
+        creatures_killed: 1,
+        tick_count: 100,
+        player_xy: (40, 1),
+        end: Some(CaveEnd::Completed),
+        score: 636,
+    });
+}
+
+// the moves of 01.txt after standing still for most of the time limit, so
+// the time runs out on the way to the exit; the rest of the replay is ignored
+#[test]
+fn map_01_out_of_time() {
+    run_golden(&Golden {
+        map: "resources/map/01.txt",
+        replay: "tests/replays/01_out_of_time.txt",
+        tiles_hash: 0x14dd1d39b76cf812,
+        diamond_count: 2,
+        creatures_killed: 0,
+        tick_count: 1125,
+        player_xy: (2, 3),
+        end: Some(CaveEnd::OutOfTime),
+        score: 20,
+    });
+}
+
+#[test]
+fn replay_keeps_its_cave() {
+    let path = std::env::temp_dir().join(format!("bd-replay-{}.txt", std::process::id()));
+    let mut replay = Replay::load(self::path("tests/replays/01.txt")).unwrap();
+    assert_eq!(replay.cave, None);
+
+    replay.cave = Some(PathBuf::from("resources/map/01.txt"));
+    replay.save(path.clone()).unwrap();
+    let loaded = Replay::load(path.clone()).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(loaded.cave, replay.cave);
+    assert_eq!(loaded.seed, replay.seed);
+    assert_eq!(loaded.ticks.len(), replay.ticks.len());
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use bd::highscores::{Entry, HighScores, TABLE_SIZE};
+use common::key;
+
+fn entry(name: &str, score: usize, ticks: u64) -> Entry {
+    Entry {
+        name: name.to_string(),
+        score,
+        ticks,
+        diamonds: score / 5,
+    }
+}
+
+fn names(scores: &HighScores, cave: &str) -> Vec<String> {
+    scores
+        .table(&key(cave))
+        .iter()
+        .map(|entry| entry.name.clone())
+        .collect()
+}
+
+#[test]
+fn best_scores_come_first() {
+    let mut scores = HighScores::default();
+    assert_eq!(scores.insert(key("01"), entry("b", 50, 300)), Some(0));
+    assert_eq!(scores.insert(key("01"), entry("a", 80, 500)), Some(0));
+    assert_eq!(scores.insert(key("01"), entry("c", 50, 200)), Some(1));
+    assert_eq!(scores.insert(key("01"), entry("d", 50, 200)), Some(2));
+
+    assert_eq!(names(&scores, "01"), ["a", "c", "d", "b"]);
+    assert!(names(&scores, "02").is_empty());
+    assert_eq!(scores.last_name, Some("d".to_string()));
+}
+
+#[test]
+fn full_table_keeps_the_best() {
+    let mut scores = HighScores::default();
+    for i in 0..TABLE_SIZE {
+        scores.insert(key("01"), entry(&i.to_string(), 10 * (i + 1), 100));
+    }
+    assert!(!scores.qualifies(&key("01"), 10));
+    assert_eq!(scores.insert(key("01"), entry("low", 5, 100)), None);
+    assert_eq!(
+        scores.insert(key("01"), entry("high", 15, 100)),
+        Some(TABLE_SIZE - 1)
+    );
+
+    let table = scores.table(&key("01"));
+    assert_eq!(table.len(), TABLE_SIZE);
+    assert_eq!(table.last().unwrap().name, "high");
+}
+
+#[test]
+fn nothing_scored_makes_no_high_score() {
+    let scores = HighScores::default();
+    assert!(!scores.qualifies(&key("01"), 0));
+    assert!(scores.qualifies(&key("01"), 5));
+}
+
+#[test]
+fn high_scores_are_saved() {
+    let mut scores = HighScores::default();
+    scores.insert(key("01"), entry("a", 80, 500));
+    let mut level_2 = key("01");
+    level_2.level = 2;
+    scores.insert(level_2.clone(), entry("b", 40, 500));
+
+    let path = std::env::temp_dir().join(format!("bd-highscores-{}.ron", std::process::id()));
+    scores.save(&path).unwrap();
+    let loaded = HighScores::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.table(&key("01")), scores.table(&key("01")));
+    assert_eq!(loaded.table(&level_2), scores.table(&level_2));
+    assert_eq!(loaded.last_name, Some("b".to_string()));
+}
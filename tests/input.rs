@@ -0,0 +1,181 @@
+use bd::input::{BufferMode, Direction, InputTracker, StickAxis};
+
+fn directions(input: &mut InputTracker) -> Vec<Direction> {
+    input
+        .pop_action()
+        .iter()
+        .map(|action| action.direction)
+        .collect()
+}
+
+#[test]
+fn stick_inside_deadzone_does_nothing() {
+    let mut input = InputTracker::default();
+    input.handle_stick(StickAxis::Horizontal, 0.1);
+    input.handle_stick(StickAxis::Vertical, -0.2);
+
+    assert!(directions(&mut input).is_empty());
+}
+
+#[test]
+fn stick_moves_towards_the_stronger_axis() {
+    let mut input = InputTracker::default();
+    input.handle_stick(StickAxis::Horizontal, 0.5);
+    input.handle_stick(StickAxis::Vertical, 0.9);
+
+    // leaning right queued a move right, then it went mostly up
+    assert_eq!(
+        directions(&mut input),
+        vec![Direction::Right, Direction::Up]
+    );
+    assert_eq!(directions(&mut input), vec![Direction::Up, Direction::Up]);
+    assert_eq!(directions(&mut input), vec![Direction::Up]);
+}
+
+#[test]
+fn stick_back_to_center_stops() {
+    let mut input = InputTracker::default();
+    input.handle_stick(StickAxis::Horizontal, -1.);
+    assert_eq!(
+        directions(&mut input),
+        vec![Direction::Left, Direction::Left]
+    );
+
+    input.handle_stick(StickAxis::Horizontal, 0.);
+    assert!(directions(&mut input).is_empty());
+}
+
+#[test]
+fn stick_fires_with_the_fire_button() {
+    let mut input = InputTracker::default();
+    input.handle_action("fire", true);
+    input.handle_stick(StickAxis::Vertical, -1.);
+
+    let actions = input.pop_action();
+    assert!(!actions.is_empty());
+    assert!(actions
+        .iter()
+        .all(|action| action.fire && action.direction == Direction::Down));
+}
+
+// Runs `events` (`L+` presses left, `U-` releases up, `F+` presses fire...),
+// taking the actions of a tick after every one of them. Every tick is
+// written as the first letters of its directions, lowercase if firing.
+fn ticks(mode: BufferMode, events: &str) -> Vec<String> {
+    let mut input = InputTracker::new(mode);
+    events
+        .split_whitespace()
+        .map(|event| {
+            let action = match &event[..1] {
+                "U" => "up",
+                "D" => "down",
+                "L" => "left",
+                "R" => "right",
+                "F" => "fire",
+                _ => panic!("unknown event {}", event),
+            };
+            input.handle_action(action, &event[1..] == "+");
+            input
+                .pop_action()
+                .iter()
+                .map(|action| {
+                    let letter = format!("{:?}", action.direction)[..1].to_string();
+                    if action.fire {
+                        letter.to_lowercase()
+                    } else {
+                        letter
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// every order of pressing and releasing two keys
+const ORDERINGS: [&str; 6] = [
+    "L+ L- U+ U-",
+    "L+ U+ L- U-",
+    "L+ U+ U- L-",
+    "U+ U- L+ L-",
+    "U+ L+ U- L-",
+    "U+ L+ L- U-",
+];
+
+fn check(mode: BufferMode, expected: [[&str; 4]; 6]) {
+    for (events, expected) in ORDERINGS.iter().zip(&expected) {
+        assert_eq!(ticks(mode, events), expected, "{:?}: {}", mode, events);
+    }
+}
+
+#[test]
+fn last_pressed_wins() {
+    check(
+        BufferMode::LastPressedWins,
+        [
+            ["LL", "", "UU", ""],
+            ["LL", "UUL", "U", ""],
+            ["LL", "UUL", "L", ""],
+            ["UU", "", "LL", ""],
+            ["UU", "LLU", "L", ""],
+            ["UU", "LLU", "U", ""],
+        ],
+    );
+}
+
+#[test]
+fn queued_taps() {
+    check(
+        BufferMode::QueuedTaps,
+        [
+            ["L", "", "U", ""],
+            ["L", "U", "", ""],
+            ["L", "U", "", ""],
+            ["U", "", "L", ""],
+            ["U", "L", "", ""],
+            ["U", "L", "", ""],
+        ],
+    );
+}
+
+#[test]
+fn one_action_per_tick() {
+    check(
+        BufferMode::OneActionPerTick,
+        [
+            ["L", "", "U", ""],
+            ["L", "U", "U", ""],
+            ["L", "U", "L", ""],
+            ["U", "", "L", ""],
+            ["U", "L", "L", ""],
+            ["U", "L", "U", ""],
+        ],
+    );
+}
+
+#[test]
+fn presses_between_ticks_are_not_lost() {
+    let mut input = InputTracker::new(BufferMode::QueuedTaps);
+    for &(action, pressed) in &[("left", true), ("left", false), ("up", true), ("up", false)] {
+        input.handle_action(action, pressed);
+    }
+
+    assert_eq!(directions(&mut input), vec![Direction::Left]);
+    assert_eq!(directions(&mut input), vec![Direction::Up]);
+    assert!(directions(&mut input).is_empty());
+}
+
+#[test]
+fn fire_applies_to_presses_and_held_keys() {
+    assert_eq!(
+        ticks(BufferMode::LastPressedWins, "F+ L+ F- U+"),
+        vec!["", "ll", "L", "UUL"]
+    );
+}
+
+#[test]
+fn release_without_press_is_ignored() {
+    assert_eq!(
+        ticks(BufferMode::LastPressedWins, "L- L+ U- L-"),
+        vec!["", "LL", "L", ""]
+    );
+}
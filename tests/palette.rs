@@ -0,0 +1,123 @@
+use bd::{
+    grid::GridState,
+    map::MapDescription,
+    palette::{parse_color, recolor, Palette, SpriteRect},
+    render::Renderer,
+    theme::DEFAULT_THEME,
+};
+
+// 2x2 image: white, grey, black, transparent
+fn sprite() -> Vec<u8> {
+    vec![
+        255, 255, 255, 255, //
+        128, 128, 128, 255, //
+        0, 0, 0, 255, //
+        200, 10, 10, 0, //
+    ]
+}
+
+const WHOLE: SpriteRect = SpriteRect {
+    x: 0,
+    y: 0,
+    width: 2,
+    height: 2,
+};
+
+#[test]
+fn recolor_keeps_shading() {
+    let mut pixels = sprite();
+    recolor(&mut pixels, 2, WHOLE, [200, 100, 0]);
+
+    assert_eq!(&pixels[0..4], &[200, 100, 0, 255]);
+    assert_eq!(&pixels[4..8], &[100, 50, 0, 255]);
+    assert_eq!(&pixels[8..12], &[0, 0, 0, 255]);
+    // transparent pixels are left alone
+    assert_eq!(&pixels[12..16], &[200, 10, 10, 0]);
+}
+
+#[test]
+fn recolor_only_touches_the_rect() {
+    let mut pixels = sprite();
+    recolor(
+        &mut pixels,
+        2,
+        SpriteRect {
+            x: 1,
+            y: 0,
+            width: 1,
+            height: 1,
+        },
+        [0, 0, 90],
+    );
+
+    let mut expected = sprite();
+    expected[4..8].copy_from_slice(&[0, 0, 90, 255]);
+    assert_eq!(pixels, expected);
+}
+
+#[test]
+fn recolor_clips_to_image() {
+    let mut pixels = sprite();
+    recolor(
+        &mut pixels,
+        2,
+        SpriteRect {
+            x: 1,
+            y: 1,
+            width: 32,
+            height: 32,
+        },
+        [0, 0, 90],
+    );
+
+    assert_eq!(pixels, sprite());
+}
+
+#[test]
+fn colors_parse() {
+    assert_eq!(parse_color("#a1683c").unwrap(), [0xa1, 0x68, 0x3c]);
+    assert!(parse_color("a1683c").is_err());
+    assert!(parse_color("#a1683").is_err());
+    assert!(parse_color("#zz683c").is_err());
+}
+
+#[test]
+fn map_header_sets_palette() {
+    let map = MapDescription::from_reader(&b"dirt_color: #010203\n###\n#s#\n###\n"[..]).unwrap();
+    assert_eq!(
+        map.params.palette,
+        Some(Palette {
+            dirt: Some([1, 2, 3]),
+            ..Palette::default()
+        })
+    );
+    assert_eq!(map.height, 3);
+
+    let map = MapDescription::from_reader(&b"###\n#s#\n###\n"[..]).unwrap();
+    assert_eq!(map.params.palette, None);
+
+    assert!(MapDescription::from_reader(&b"dirt_colour: #010203\n###\n#s#\n###\n"[..]).is_err());
+}
+
+#[test]
+fn palette_leaves_other_sprites_alone() {
+    let map = MapDescription::from_reader(&b"dirt_color: #010203\n#o.s\n"[..]).unwrap();
+    let palette = map.params.palette;
+    let grid = GridState::from_map(map, 0);
+
+    let plain = Renderer::load(DEFAULT_THEME, None).unwrap();
+    let painted = Renderer::load(DEFAULT_THEME, palette).unwrap();
+    let plain = plain.render(&grid, 0.);
+    let painted = painted.render(&grid, 0.);
+    let size = plain.height();
+    let cell = |image: &image::RgbaImage, x: u32| -> Vec<[u8; 4]> {
+        (0..size * size)
+            .map(|i| image.get_pixel(x * size + i % size, i / size).data)
+            .collect()
+    };
+
+    // steel and rock keep their colours, dirt doesn't
+    assert_eq!(cell(&painted, 0), cell(&plain, 0));
+    assert_eq!(cell(&painted, 1), cell(&plain, 1));
+    assert_ne!(cell(&painted, 2), cell(&plain, 2));
+}
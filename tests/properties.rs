@@ -0,0 +1,206 @@
+//! Randomized checks of map loading and the rules engine invariants
+use bd::{
+    grid::{CreatureKind, GridPos, GridState, Outcome, TileType},
+    input::{Action, Direction},
+    map::MapDescription,
+};
+use proptest::prelude::*;
+
+fn direction() -> impl Strategy<Value = Direction> {
+    prop_oneof![
+        Just(Direction::Up),
+        Just(Direction::Down),
+        Just(Direction::Left),
+        Just(Direction::Right),
+    ]
+}
+
+fn creature_kind() -> impl Strategy<Value = CreatureKind> {
+    prop_oneof![Just(CreatureKind::Firefly), Just(CreatureKind::Butterfly)]
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    (any::<bool>(), direction()).prop_map(|(fire, direction)| Action { fire, direction })
+}
+
+fn inner_tile() -> impl Strategy<Value = TileType> {
+    prop_oneof![
+        4 => Just(TileType::Empty),
+        4 => Just(TileType::Dirt),
+        3 => Just(TileType::Rock),
+        2 => Just(TileType::Diamond),
+        1 => Just(TileType::Wall),
+        1 => Just(TileType::Steel),
+        1 => Just(TileType::Exit),
+        1 => (creature_kind(), 0..3usize, direction())
+            .prop_map(|(kind, counter, direction)| TileType::Creature {
+                kind,
+                counter,
+                direction,
+            }),
+    ]
+}
+
+// a map surrounded by steel, with a single player somewhere inside
+fn map() -> impl Strategy<Value = MapDescription> {
+    (3..16usize, 3..16usize)
+        .prop_flat_map(|(width, height)| {
+            (
+                Just(width),
+                Just(height),
+                prop::collection::vec(inner_tile(), width * height),
+                1..width - 1,
+                1..height - 1,
+            )
+        })
+        .prop_map(|(width, height, mut tiles, start_x, start_y)| {
+            for (i, tile) in tiles.iter_mut().enumerate() {
+                let (x, y) = GridPos::new(i).to_xy(width);
+                if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+                    *tile = TileType::Steel;
+                }
+            }
+            let start = GridPos::from_xy(start_x, start_y, width);
+            tiles[start_x + start_y * width] = TileType::Player;
+
+            MapDescription {
+                tiles,
+                width,
+                height,
+                start,
+                params: Default::default(),
+            }
+        })
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Counts {
+    player: usize,
+    dirt: usize,
+    rock: usize,
+    wall: usize,
+    diamond: usize,
+    steel: usize,
+    exit: usize,
+    creature: usize,
+}
+
+fn count(grid: &GridState) -> Counts {
+    let mut counts = Counts::default();
+    for tile in grid.tiles() {
+        match tile {
+            TileType::Empty => {}
+            TileType::Player => counts.player += 1,
+            TileType::Dirt => counts.dirt += 1,
+            TileType::Rock => counts.rock += 1,
+            TileType::Wall => counts.wall += 1,
+            TileType::Diamond => counts.diamond += 1,
+            TileType::Steel => counts.steel += 1,
+            TileType::Exit => counts.exit += 1,
+            TileType::Creature { .. } => counts.creature += 1,
+        }
+    }
+    counts
+}
+
+proptest! {
+    #[test]
+    fn load_never_panics(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+        let _ = MapDescription::from_reader(&bytes[..]);
+    }
+
+    #[test]
+    fn load_text_never_panics(text in "[#%.o*sxbX \n]{0,256}") {
+        let _ = MapDescription::from_reader(text.as_bytes());
+    }
+
+    #[test]
+    fn loaded_maps_can_be_played(
+        text in "[#%.o*sxbX \n]{0,256}",
+        ticks in prop::collection::vec(prop::collection::vec(action(), 0..3), 0..32),
+    ) {
+        if let Ok(map) = MapDescription::from_reader(text.as_bytes()) {
+            let mut grid = GridState::from_map(map, 0);
+            for actions in ticks {
+                grid.run_tick(actions);
+            }
+        }
+    }
+
+    #[test]
+    fn tick_keeps_invariants(
+        map in map(),
+        seed in any::<u64>(),
+        ticks in prop::collection::vec(prop::collection::vec(action(), 0..3), 0..64),
+    ) {
+        let mut grid = GridState::from_map(map, seed);
+
+        for actions in ticks {
+            let before = count(&grid);
+            let diamonds_before = grid.diamond_count;
+            let killed_before = grid.creatures_killed;
+            let tiles_before = grid.tiles().to_vec();
+            grid.run_tick(actions);
+            let after = count(&grid);
+
+            // every changed cell has to be reported
+            let reported: Vec<_> = grid
+                .events
+                .iter()
+                .flat_map(|event| event.positions())
+                .map(GridPos::index)
+                .collect();
+            for (i, (old, new)) in tiles_before.iter().zip(grid.tiles()).enumerate() {
+                if old != new {
+                    prop_assert!(reported.contains(&i), "cell {} changed unreported", i);
+                }
+            }
+
+            match grid.outcome() {
+                Outcome::Died => prop_assert_eq!(after.player, 0),
+                _ => {
+                    prop_assert_eq!(after.player, 1);
+                    prop_assert_eq!(grid.get_tile(grid.player_pos), TileType::Player);
+                }
+            }
+
+            // diamonds only go away by being collected
+            prop_assert_eq!(
+                before.diamond + diamonds_before,
+                after.diamond + grid.diamond_count
+            );
+            // dirt can only be dug, and the exit only entered
+            prop_assert!(after.dirt <= before.dirt);
+            if grid.outcome() != Outcome::Completed {
+                prop_assert_eq!(after.exit, before.exit);
+            }
+            prop_assert_eq!(after.rock, before.rock);
+            prop_assert_eq!(after.wall, before.wall);
+            prop_assert_eq!(after.steel, before.steel);
+            // creatures only go away by being crushed
+            prop_assert_eq!(
+                after.creature + grid.creatures_killed,
+                before.creature + killed_before
+            );
+        }
+    }
+
+    #[test]
+    fn sleeping_cells_match_full_scan(
+        map in map(),
+        seed in any::<u64>(),
+        ticks in prop::collection::vec(prop::collection::vec(action(), 0..3), 0..64),
+    ) {
+        let mut sparse = GridState::from_map(map.clone(), seed);
+        let mut full = GridState::from_map(map, seed);
+
+        for actions in ticks {
+            sparse.run_tick(actions.clone());
+            full.wake_all();
+            full.run_tick(actions);
+
+            prop_assert_eq!(sparse.tiles(), full.tiles());
+            prop_assert_eq!(&sparse.events, &full.events);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+mod common;
+
+use bd::{
+    grid::GridState,
+    input::Direction,
+    map::MapDescription,
+    palette::SpriteRect,
+    render::{GifWriter, Renderer},
+    theme::{Theme, DEFAULT_THEME},
+};
+use common::{grid, walk};
+use image::{Rgba, RgbaImage};
+use std::{path::Path, time::Duration};
+
+const COLORS: [[u8; 4]; 4] = [
+    [255, 0, 0, 255],
+    [0, 255, 0, 255],
+    [0, 0, 255, 255],
+    [255, 255, 0, 255],
+];
+
+// four 2x2 sprites of solid colours, side by side
+fn renderer() -> Renderer {
+    let sheet = RgbaImage::from_fn(8, 2, |x, _| Rgba(COLORS[x as usize / 2]));
+    let rects = (0..4)
+        .map(|i| SpriteRect {
+            x: i * 2,
+            y: 0,
+            width: 2,
+            height: 2,
+        })
+        .collect();
+
+    let mut theme = Theme::default();
+    theme.tiles.insert("player".into(), 0);
+    theme.tiles.insert("dirt".into(), 1);
+    theme.tiles.insert("steel".into(), 2);
+    theme.tiles.insert("diamond".into(), 1);
+    theme.frame_duration = 1.;
+    theme.animations.insert("diamond".into(), vec![1, 3]);
+    theme.animations.insert("player_left".into(), vec![3]);
+
+    Renderer::new(sheet, rects, theme)
+}
+
+// colour of the cell `x` from the left, `row` from the top
+fn cell(image: &RgbaImage, x: u32, row: u32) -> [u8; 4] {
+    image.get_pixel(x * 2, row * 2).data
+}
+
+#[test]
+fn renders_top_row_first() {
+    let grid = grid("####\n#s.#\n#. #\n####\n");
+    let image = renderer().render(&grid, 0.);
+
+    assert_eq!(image.dimensions(), (8, 8));
+    assert_eq!(cell(&image, 0, 0), COLORS[2]);
+    assert_eq!(cell(&image, 1, 1), COLORS[0]);
+    assert_eq!(cell(&image, 2, 1), COLORS[1]);
+    assert_eq!(cell(&image, 1, 2), COLORS[1]);
+    // empty cells stay black
+    assert_eq!(cell(&image, 2, 2), [0, 0, 0, 255]);
+}
+
+#[test]
+fn animation_frames_follow_time() {
+    let grid = grid("####\n#s*#\n####\n");
+    let renderer = renderer();
+
+    assert_eq!(cell(&renderer.render(&grid, 0.5), 2, 1), COLORS[1]);
+    assert_eq!(cell(&renderer.render(&grid, 1.5), 2, 1), COLORS[3]);
+    assert_eq!(cell(&renderer.render(&grid, 2.5), 2, 1), COLORS[1]);
+}
+
+#[test]
+fn walking_player_is_animated() {
+    let mut grid = grid("#####\n# .s#\n#####\n");
+    let renderer = renderer();
+    assert_eq!(cell(&renderer.render(&grid, 0.), 3, 1), COLORS[0]);
+
+    walk(&mut grid, Direction::Left, 1);
+    assert_eq!(cell(&renderer.render(&grid, 0.), 2, 1), COLORS[3]);
+}
+
+// the bundled theme, with the cave palette, compared to a stored image;
+// run with UPDATE_SNAPSHOTS=1 to store a new one
+#[test]
+fn snapshot_of_small_cave() {
+    let map = MapDescription::load("tests/snapshots/small.txt".into()).unwrap();
+    let grid = GridState::from_map(map, 0);
+    let renderer = Renderer::load(DEFAULT_THEME, grid.params().palette).unwrap();
+    let image = renderer.render(&grid, 0.5);
+
+    let path = Path::new("tests/snapshots/small.png");
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        image.save(path).unwrap();
+    }
+
+    let expected = image::open(path).unwrap().to_rgba();
+    if expected.dimensions() != image.dimensions() || *expected != *image {
+        let actual = std::env::temp_dir().join("small.png");
+        image.save(&actual).unwrap();
+        panic!("rendering differs from {:?}, see {:?}", path, actual);
+    }
+}
+
+#[test]
+fn gif_frames_keep_the_tick_rate() {
+    let mut grid = grid("#####\n# .s#\n#####\n");
+    let renderer = renderer();
+    // 13.3 hundredths of a second
+    let tick = bd::game::tick_duration();
+    let (width, height) = renderer.render(&grid, 0.).dimensions();
+
+    let mut data = vec![];
+    {
+        let mut writer = GifWriter::new(&mut data, width, height).unwrap();
+        writer.add_frame(&renderer.render(&grid, 0.), tick).unwrap();
+        for _ in 0..2 {
+            walk(&mut grid, Direction::Left, 1);
+            writer.add_frame(&renderer.render(&grid, 0.), tick).unwrap();
+        }
+    }
+
+    let mut reader = gif::Decoder::new(&data[..]).read_info().unwrap();
+    let mut delays = vec![];
+    while let Some(frame) = reader.read_next_frame().unwrap() {
+        delays.push(frame.delay);
+    }
+    assert_eq!(delays, vec![13, 14, 13]);
+}
+
+#[test]
+fn gif_too_big_is_an_error() {
+    let mut data = vec![];
+    assert!(GifWriter::new(&mut data, 70_000, 6).is_err());
+
+    assert!(GifWriter::new(&mut data, 10, 70_000).is_err());
+}
+
+#[test]
+fn gif_frames_of_another_size_are_an_error() {
+    let mut data = vec![];
+    let mut writer = GifWriter::new(&mut data, 10, 6).unwrap();
+    let tick = Duration::from_millis(10);
+
+    assert!(writer.add_frame(&RgbaImage::new(1, 70_000), tick).is_err());
+    assert!(writer.add_frame(&RgbaImage::new(6, 10), tick).is_err());
+    writer.add_frame(&RgbaImage::new(10, 6), tick).unwrap();
+    // nor after a frame that did fit
+    assert!(writer.add_frame(&RgbaImage::new(10, 5), tick).is_err());
+    writer.add_frame(&RgbaImage::new(10, 6), tick).unwrap();
+}
+
+#[test]
+fn preview_keeps_proportions() {
+    let grid = grid("######\n#s...#\n######\n");
+    let image = renderer().preview(&grid, 6, 6);
+
+    assert_eq!(image.dimensions(), (6, 3));
+    assert_eq!(image.get_pixel(0, 0).data, COLORS[2]);
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use bd::{game, grid::Outcome, input::Direction};
+use common::{grid, walk};
+
+#[test]
+fn diamonds_past_the_ones_needed_are_worth_more() {
+    let mut grid = grid(
+        "diamonds_needed: 2\ndiamond_value: 10\nextra_diamond_value: 25\n\
+         #######\n#s***X#\n#######\n",
+    );
+    walk(&mut grid, Direction::Right, 2);
+    assert_eq!(game::score(&grid), 20);
+    walk(&mut grid, Direction::Right, 1);
+    assert_eq!(game::score(&grid), 45);
+}
+
+#[test]
+fn time_left_counts_once_completed() {
+    let mut grid = grid(
+        "diamond_value: 10\nextra_diamond_value: 10\ntime: 100\ntime_bonus: 3\n\
+         #####\n#s*X#\n#####\n",
+    );
+    walk(&mut grid, Direction::Right, 1);
+    assert_eq!(game::score(&grid), 10);
+
+    walk(&mut grid, Direction::Right, 1);
+    assert_eq!(grid.outcome(), Outcome::Completed);
+    let seconds_left = game::time_left(&grid).unwrap().as_secs() as usize;
+    assert_eq!(seconds_left, 99);
+    assert_eq!(game::score(&grid), 10 + 3 * seconds_left);
+}
+
+#[test]
+fn falling_rocks_crush_creatures() {
+    let mut grid = grid("creature_value: 100\n#####\n#so #\n#   #\n#% %#\n#%x%#\n#####\n");
+    walk(&mut grid, Direction::Down, 8);
+
+    assert_eq!(grid.creatures_killed, 1);
+    assert_eq!(game::score(&grid), 100);
+    assert!(!grid.tiles().iter().any(|tile| tile.kind_name() == "firefly"));
+}
+
+#[test]
+fn resting_rocks_leave_creatures_alone() {
+    let mut grid = grid("creature_value: 100\n#####\n#s  #\n#%o%#\n#%x%#\n#####\n");
+    walk(&mut grid, Direction::Down, 8);
+
+    assert_eq!(grid.creatures_killed, 0);
+    assert_eq!(game::score(&grid), 0);
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use bd::{
+    game,
+    session::{self, CaveEnd, CaveResult, Session},
+};
+use common::grid;
+
+fn result(end: CaveEnd, score: usize) -> CaveResult {
+    CaveResult { end, score }
+}
+
+#[test]
+fn completing_caves_moves_through_them_and_the_levels() {
+    let mut session = Session::new(2);
+    assert_eq!(session.intro(), "Cave A, Level 1");
+
+    session.cave_over(result(CaveEnd::Completed, 10));
+    assert_eq!(session.intro(), "Cave B, Level 1");
+    session.cave_over(result(CaveEnd::Completed, 20));
+    assert_eq!(session.intro(), "Cave A, Level 2");
+
+    assert_eq!(session.score, 30);
+    assert_eq!(session.lives, session::START_LIVES);
+}
+
+#[test]
+fn losing_every_life_ends_the_game() {
+    let mut session = Session::new(2);
+    session.cave_over(result(CaveEnd::Died, 15));
+    session.cave_over(result(CaveEnd::OutOfTime, 0));
+    assert_eq!(session.intro(), "Cave A, Level 1");
+    assert_eq!(session.score, 15);
+    assert!(!session.is_over());
+
+    session.cave_over(result(CaveEnd::Died, 0));
+    assert!(session.is_over());
+}
+
+#[test]
+fn extra_life_every_500_points() {
+    let mut session = Session::new(1);
+    session.cave_over(result(CaveEnd::Completed, 495));
+    assert_eq!(session.lives, session::START_LIVES);
+    session.cave_over(result(CaveEnd::Died, 10));
+    assert_eq!(session.lives, session::START_LIVES);
+    session.cave_over(result(CaveEnd::Completed, 1000));
+    assert_eq!(session.lives, session::START_LIVES + 2);
+}
+
+#[test]
+fn quitting_keeps_lives_and_score() {
+    let mut session = Session::new(1);
+    session.cave_over(result(CaveEnd::Quit, 100));
+    assert_eq!(session.score, 0);
+    assert_eq!(session.lives, session::START_LIVES);
+}
+
+#[test]
+fn cave_stands_still_when_out_of_time() {
+    let mut grid = grid("time: 1\n####\n#s #\n####\n");
+    let mut input = Default::default();
+    let mut replay = Default::default();
+
+    while !game::out_of_time(&grid) {
+        game::run_tick(&mut grid, &mut input, &mut replay);
+    }
+    let ticks = grid.tick_count;
+    assert_eq!(game::cave_time(ticks).as_secs(), 1);
+    assert_eq!(game::time_left(&grid), Some(Default::default()));
+
+    game::run_tick(&mut grid, &mut input, &mut replay);
+    assert_eq!(grid.tick_count, ticks);
+}
@@ -0,0 +1,88 @@
+use bd::{
+    grid::{CreatureKind, TileType},
+    input::Direction,
+    theme::{self, Theme},
+};
+
+const VISIBLE_TILES: [TileType; 9] = [
+    TileType::Player,
+    TileType::Dirt,
+    TileType::Rock,
+    TileType::Wall,
+    TileType::Diamond,
+    TileType::Steel,
+    TileType::Exit,
+    TileType::Creature {
+        kind: CreatureKind::Firefly,
+        counter: 0,
+        direction: Direction::Up,
+    },
+    TileType::Creature {
+        kind: CreatureKind::Butterfly,
+        counter: 0,
+        direction: Direction::Up,
+    },
+];
+
+#[test]
+fn default_theme_is_bundled() {
+    let themes = theme::list().unwrap();
+    assert!(themes.iter().any(|name| name == theme::DEFAULT_THEME));
+}
+
+#[test]
+fn bundled_themes_are_complete() {
+    for name in theme::list().unwrap() {
+        let theme = Theme::load(&name).unwrap();
+        let sprites = theme::sprite_rects(&name).unwrap().len();
+
+        assert_eq!(theme.sprite_for(TileType::Empty), None, "{}", name);
+        for tile in &VISIBLE_TILES {
+            let sprite = theme.sprite_for(*tile);
+            assert!(sprite.is_some(), "{} has no sprite for {:?}", name, tile);
+            assert!(sprite.unwrap() < sprites, "{}: {:?}", name, tile);
+        }
+        for (animation, frames) in &theme.animations {
+            for frame in frames {
+                assert!(*frame < sprites, "{}: {} frame {}", name, animation, frame);
+            }
+        }
+    }
+}
+
+#[test]
+fn default_theme_animates_walking_and_creatures() {
+    let theme = Theme::load(theme::DEFAULT_THEME).unwrap();
+    let player = TileType::Player;
+    assert_eq!(
+        theme.animation_for(player, Some(Direction::Left)),
+        Some("player_left")
+    );
+    assert_eq!(
+        theme.animation_for(player, Some(Direction::Right)),
+        Some("player_right")
+    );
+    assert_eq!(theme.animation_for(player, None), Some("player"));
+    for tile in &VISIBLE_TILES[7..] {
+        assert_eq!(theme.animation_for(*tile, None), Some(tile.kind_name()));
+    }
+}
+
+#[test]
+fn default_theme_blinks_and_taps_when_idle() {
+    let theme = Theme::load(theme::DEFAULT_THEME).unwrap();
+    let mut frames = theme.animations["player"].clone();
+    frames.sort();
+    frames.dedup();
+    // standing, blinking and tapping
+    assert_eq!(frames.len(), 3);
+}
+
+#[test]
+fn bundled_themes_include_a_hi_res_one() {
+    let themes = theme::list().unwrap();
+    assert_eq!(themes, ["classic", "high_contrast", "modern"]);
+    let size = |name: &str| theme::tile_size(&theme::sprite_rects(name).unwrap());
+    assert_eq!(size("classic"), 32);
+    assert_eq!(size("modern"), 64);
+}